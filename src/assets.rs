@@ -0,0 +1,301 @@
+use crate::files;
+use axum::{
+    extract::{Request, State},
+    http::{HeaderValue, StatusCode, Uri, header::CACHE_CONTROL},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use percent_encoding::percent_decode_str;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    io,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::SystemTime,
+};
+
+// Fingerprinted static assets. At startup every file under the static directory is
+// hashed and given a versioned name, `css/electric-eclipse/main.css` becoming
+// `css/electric-eclipse/main.3f9a1c0b2d.css`; views link to that name through
+// `asset_url`, so a changed file gets a new URL and can be cached forever. The plain
+// paths keep working (favicons, the web manifest, old links) with a short cache. Names
+// are hashed once at startup: a file changed on disk afterwards is still served under its
+// old name until a restart, but with the short cache, so browsers don't keep it forever.
+// The data directory lives under the static one by default; its files (content, drafts
+// included) are read by the server and never served.
+
+/// Fingerprinted URLs never change content
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+/// Plain URLs may change on the next deploy
+const SHORT_CACHE: &str = "public, max-age=300";
+/// Hex digits of the content hash in file names
+const HASH_LENGTH: usize = 10;
+
+static MANIFEST: OnceLock<Manifest> = OnceLock::new();
+
+struct Manifest {
+    static_dir: PathBuf,
+    /// `css/site.css` -> `css/site.0123456789.css`
+    fingerprinted: HashMap<String, String>,
+    /// The reverse, to serve the file behind a fingerprinted name
+    originals: HashMap<String, String>,
+    /// Modification time of each file when it was hashed
+    modified: HashMap<String, Option<SystemTime>>,
+}
+
+impl Manifest {
+    // Hash every file of `static_dir` outside `data_dir`
+    fn build(static_dir: &Path, data_dir: &Path) -> io::Result<Self> {
+        let mut manifest = Manifest {
+            static_dir: static_dir.to_path_buf(),
+            fingerprinted: HashMap::new(),
+            originals: HashMap::new(),
+            modified: HashMap::new(),
+        };
+        for path in files::walk(static_dir)? {
+            if path.starts_with(data_dir) {
+                continue;
+            }
+            let Some(relative) = path.strip_prefix(static_dir).ok().and_then(Path::to_str) else {
+                continue;
+            };
+            // URL paths, also on Windows
+            let relative = relative.replace('\\', "/");
+            // Read before the contents, so a change while hashing counts as a change
+            manifest.modified.insert(relative.clone(), files::modified(&path));
+            let fingerprinted = fingerprint(&relative, &files::read(&path)?);
+            manifest.originals.insert(fingerprinted.clone(), relative.clone());
+            manifest.fingerprinted.insert(relative, fingerprinted);
+        }
+        Ok(manifest)
+    }
+
+    // The file behind a fingerprinted name, and how long it may be cached
+    fn resolve(&self, path: &str) -> Option<(&str, &'static str)> {
+        let original = self.originals.get(path)?;
+        // Changed on disk since it was hashed: the name no longer matches the contents
+        let current = files::modified(&self.static_dir.join(original));
+        let unchanged = self.modified.get(original).is_some_and(|hashed| *hashed == current);
+        Some((original, if unchanged { IMMUTABLE } else { SHORT_CACHE }))
+    }
+}
+
+/// Hash the files of `static_dir`, leaving out `data_dir` (content files change while
+/// the server runs and are not linked from pages)
+pub fn load_manifest(static_dir: &Path, data_dir: &Path) -> io::Result<()> {
+    let manifest = Manifest::build(static_dir, data_dir)?;
+    tracing::info!("Fingerprinted {} static files", manifest.fingerprinted.len());
+
+    // Pages already link to the names of the first manifest, so those keep being served
+    let _ = MANIFEST.set(manifest);
+    Ok(())
+}
+
+/// URL of a file under the static directory, fingerprinted when it was hashed at
+/// startup: `asset_url("js/main.js")` is `/static/js/main.0123456789.js`
+pub fn asset_url(path: &str) -> String {
+    let fingerprinted = MANIFEST.get().and_then(|manifest| manifest.fingerprinted.get(path));
+    format!("/static/{}", fingerprinted.map_or(path, String::as_str))
+}
+
+/// Part of the static directory that is not served: the data directory, when it is
+/// inside the static one
+#[derive(Debug, Clone, Default)]
+pub struct PrivateDir(Option<PathBuf>);
+
+impl PrivateDir {
+    pub fn new(static_dir: &Path, data_dir: &Path) -> Self {
+        let relative = data_dir.strip_prefix(static_dir).map(Path::to_path_buf).ok().or_else(|| {
+            // One given as an absolute path and the other relative
+            let data_dir = data_dir.canonicalize().ok()?;
+            Some(data_dir.strip_prefix(static_dir.canonicalize().ok()?).ok()?.to_path_buf())
+        });
+        PrivateDir(relative)
+    }
+
+    // Whether the path, relative to the static directory, points inside it. Decoded like
+    // the file service does, so `%64ata/` is `data/` too
+    fn contains(&self, path: &str) -> bool {
+        let Some(private) = &self.0 else {
+            return false;
+        };
+        let path = percent_decode_str(path).decode_utf8_lossy();
+        Path::new(path.trim_start_matches('/')).starts_with(private)
+    }
+}
+
+/// Middleware for the static file service: keeps the data directory out, maps
+/// fingerprinted names back to the file and sets how long the response can be cached
+pub async fn serve(State(private): State<PrivateDir>, mut request: Request, next: Next) -> Response {
+    // Nested under /static, the path is relative to the static directory
+    let path = request.uri().path().trim_start_matches('/');
+    if private.contains(path) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    let cache = route(MANIFEST.get(), &mut request);
+
+    let mut response = next.run(request).await;
+    if response.status().is_success() || response.status().is_redirection() {
+        response.headers_mut().insert(CACHE_CONTROL, HeaderValue::from_static(cache));
+    }
+    response
+}
+
+// Points a request for a fingerprinted name at the file; returns how long the response
+// may be cached
+fn route(manifest: Option<&Manifest>, request: &mut Request) -> &'static str {
+    let path = request.uri().path().trim_start_matches('/');
+    let resolved = manifest.and_then(|manifest| manifest.resolve(path));
+    match resolved.and_then(|(original, cache)| Some((with_path(request.uri(), original)?, cache))) {
+        Some((uri, cache)) => {
+            *request.uri_mut() = uri;
+            cache
+        }
+        None => SHORT_CACHE,
+    }
+}
+
+// The hash goes before the extension, so the content type is still guessed from it
+fn fingerprint(path: &str, contents: &[u8]) -> String {
+    let mut hash = String::new();
+    for byte in Sha256::digest(contents) {
+        let _ = write!(hash, "{:02x}", byte);
+    }
+    hash.truncate(HASH_LENGTH);
+
+    let name_start = path.rfind('/').map_or(0, |i| i + 1);
+    match path[name_start..].rfind('.').filter(|&i| i > 0) {
+        Some(dot) => format!("{}.{}{}", &path[..name_start + dot], hash, &path[name_start + dot..]),
+        None => format!("{}.{}", path, hash),
+    }
+}
+
+fn with_path(uri: &Uri, path: &str) -> Option<Uri> {
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = Some(format!("/{}", path).parse().ok()?);
+    Uri::from_parts(parts).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Body, middleware};
+    use std::{fs, future::poll_fn, time::Duration};
+    use tower::Service;
+    use tower_http::services::ServeDir;
+
+    // The repository's static directory, served like in `main`
+    fn static_files() -> Router {
+        let static_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("static");
+        let private = PrivateDir::new(&static_dir, &static_dir.join("data"));
+        Router::new()
+            .fallback_service(ServeDir::new(&static_dir))
+            .layer(middleware::from_fn_with_state(private, serve))
+    }
+
+    async fn get(app: &mut Router, path: &str) -> Response {
+        let request = Request::get(path).body(Body::empty()).unwrap();
+        let Ok(()) = poll_fn(|cx| Service::<Request>::poll_ready(app, cx)).await;
+        let Ok(response) = app.call(request).await;
+        response
+    }
+
+    #[tokio::test]
+    async fn data_files_are_not_served() {
+        let mut app = static_files();
+        // A draft post: hidden from the blog, so its source must not be readable either
+        for path in ["/data/blog/notas-sobre-sqlite.md", "/%64ata/blog/notas-sobre-sqlite.md", "/data%2Fprofile.json"] {
+            assert_eq!(get(&mut app, path).await.status(), StatusCode::NOT_FOUND, "{}", path);
+        }
+        assert_eq!(get(&mut app, "/css/electric-eclipse/main.css").await.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn private_dir_is_relative_to_static_dir() {
+        let private = PrivateDir::new(Path::new("static"), Path::new("static/data"));
+        assert!(private.contains("data/blog/post.md"));
+        assert!(private.contains("/data/person.json"));
+        assert!(!private.contains("database.js"));
+        assert!(!private.contains("css/data/site.css"));
+
+        // Outside the static directory, nothing there is private
+        let private = PrivateDir::new(Path::new("static"), Path::new("content"));
+        assert!(!private.contains("data/blog/post.md"));
+    }
+
+    // A static directory of its own for a test, removed when dropped
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("basic-web-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(dir.join("css")).unwrap();
+            fs::create_dir_all(dir.join("data")).unwrap();
+            fs::write(dir.join("data/profile.json"), "{}").unwrap();
+            TempDir(dir)
+        }
+
+        fn manifest(&self) -> Manifest {
+            Manifest::build(&self.0, &self.0.join("data")).unwrap()
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn cache_for(manifest: &Manifest, uri: &str) -> (&'static str, String) {
+        let mut request = Request::get(uri).body(Body::empty()).unwrap();
+        let cache = route(Some(manifest), &mut request);
+        (cache, request.uri().to_string())
+    }
+
+    #[test]
+    fn fingerprint_goes_before_extension() {
+        let name = fingerprint("css/site.css", b"p {}");
+        assert!(name.starts_with("css/site.") && name.ends_with(".css"), "{}", name);
+        assert_eq!(name.len(), "css/site..css".len() + HASH_LENGTH);
+        assert_eq!(fingerprint("css/site.css", b"p {}"), name);
+        assert_eq!(fingerprint("LICENSE", b"MIT").len(), "LICENSE.".len() + HASH_LENGTH);
+        // A leading dot is not an extension
+        assert!(fingerprint(".well-known/security", b"").starts_with(".well-known/security."));
+    }
+
+    #[test]
+    fn changed_file_gets_new_url() {
+        let dir = TempDir::new("assets-changed");
+        fs::write(dir.0.join("css/site.css"), "p { color: red }").unwrap();
+        let before = dir.manifest();
+        fs::write(dir.0.join("css/site.css"), "p { color: blue }").unwrap();
+        let after = dir.manifest();
+
+        let url = |manifest: &Manifest| manifest.fingerprinted["css/site.css"].clone();
+        assert_ne!(url(&before), url(&after));
+        assert_eq!(after.originals[&url(&after)], "css/site.css");
+        // Content files aren't linked from pages
+        assert!(!after.fingerprinted.contains_key("data/profile.json"));
+    }
+
+    #[test]
+    fn only_fingerprinted_urls_are_immutable() {
+        let dir = TempDir::new("assets-immutable");
+        let file = dir.0.join("css/site.css");
+        fs::write(&file, "p {}").unwrap();
+        let manifest = dir.manifest();
+        let fingerprinted = format!("/{}", manifest.fingerprinted["css/site.css"]);
+
+        assert_eq!(cache_for(&manifest, &fingerprinted), (IMMUTABLE, "/css/site.css".to_string()));
+        assert_eq!(cache_for(&manifest, "/css/site.css"), (SHORT_CACHE, "/css/site.css".to_string()));
+        assert_eq!(cache_for(&manifest, "/css/site.0123456789.css").0, SHORT_CACHE);
+
+        // Edited after it was hashed: still found, but no longer cached for good
+        let later = SystemTime::now() + Duration::from_secs(60);
+        fs::File::options().write(true).open(&file).unwrap().set_modified(later).unwrap();
+        assert_eq!(cache_for(&manifest, &fingerprinted), (SHORT_CACHE, "/css/site.css".to_string()));
+    }
+}
@@ -0,0 +1,77 @@
+use crate::config::Config;
+use crate::i18n::Lang;
+use crate::state::AppState;
+use axum::{
+    extract::{Request, State},
+    http::{
+        StatusCode,
+        header::{AUTHORIZATION, WWW_AUTHENTICATE},
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+
+// HTTP Basic authentication for the /admin pages
+
+#[derive(Clone)]
+pub struct AdminCredentials {
+    pub username: String,
+    pub password: String,
+}
+
+impl AdminCredentials {
+    /// From `admin_user` and `admin_password`. Without a password the admin pages are disabled.
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let password = config.admin_password.as_ref()?.expose().to_string();
+        Some(AdminCredentials {
+            username: config.admin_user.clone(),
+            password,
+        })
+    }
+
+    fn matches(&self, username: &str, password: &str) -> bool {
+        // Evaluate both comparisons so timing doesn't reveal which one failed
+        let user_ok = constant_time_eq(self.username.as_bytes(), username.as_bytes());
+        let pass_ok = constant_time_eq(self.password.as_bytes(), password.as_bytes());
+        user_ok & pass_ok
+    }
+}
+
+pub async fn require_admin(State(state): State<AppState>, lang: Lang, request: Request, next: Next) -> Response {
+    let Some(credentials) = state.admin.as_ref() else {
+        return crate::not_found(State(state), lang).await.into_response();
+    };
+
+    let authorized = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_basic)
+        .is_some_and(|(user, pass)| credentials.matches(&user, &pass));
+
+    if authorized {
+        next.run(request).await
+    } else {
+        (
+            StatusCode::UNAUTHORIZED,
+            [(WWW_AUTHENTICATE, "Basic realm=\"admin\", charset=\"UTF-8\"")],
+            "Authentication required",
+        )
+            .into_response()
+    }
+}
+
+fn parse_basic(header: &str) -> Option<(String, String)> {
+    let encoded = header.strip_prefix("Basic ")?;
+    let decoded = String::from_utf8(STANDARD.decode(encoded.trim()).ok()?).ok()?;
+    let (user, pass) = decoded.split_once(':')?;
+    Some((user.to_string(), pass.to_string()))
+}
+
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
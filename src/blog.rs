@@ -0,0 +1,282 @@
+use crate::content::ContentError;
+use crate::files;
+use crate::i18n::Locale;
+use chrono::NaiveDate;
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd, html};
+use serde::Deserialize;
+use std::{collections::BTreeMap, path::Path, sync::LazyLock};
+use syntect::{highlighting::ThemeSet, html::highlighted_html_for_string, parsing::SyntaxSet};
+
+// Blog posts: Markdown files with TOML front matter in the `blog` folder of the data
+// directory. `slug.md` is the default language, `slug.en.md` its English version.
+//
+//     +++
+//     title = "Título"
+//     date = 2024-11-20
+//     tags = ["rust", "htmx"]
+//     summary = "Shown in the post list"
+//     draft = true
+//     +++
+
+pub const POSTS_PER_PAGE: usize = 5;
+
+/// Drafts are only listed while developing
+const SHOW_DRAFTS: bool = cfg!(debug_assertions);
+
+// Colors are inlined, so code blocks look the same in the light and dark themes
+const CODE_THEME: &str = "base16-ocean.dark";
+
+static SYNTAXES: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+static THEMES: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FrontMatter {
+    title: String,
+    date: toml::value::Datetime,
+    summary: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    draft: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Post {
+    pub slug: String,
+    pub title: String,
+    pub date: NaiveDate,
+    pub summary: String,
+    /// Lowercase, with spaces turned into hyphens so they work in URLs
+    pub tags: Vec<String>,
+    pub draft: bool,
+    /// Body rendered to HTML, code blocks highlighted
+    pub html: String,
+    /// Prose of the body without markup or code blocks, for the search index
+    pub text: String,
+}
+
+/// Published posts, newest first
+#[derive(Debug, Clone, Default)]
+pub struct Blog {
+    posts: Vec<Post>,
+}
+
+/// One page of a post list
+pub struct PostPage<'a> {
+    pub posts: &'a [&'a Post],
+    pub number: usize,
+    pub total_pages: usize,
+}
+
+impl Blog {
+    /// A missing `blog` folder means there are no posts yet
+    pub fn load(dir: &Path, locale: Locale) -> Result<Self, ContentError> {
+        let dir = dir.join("blog");
+        let Ok(filenames) = files::file_names(&dir) else {
+            return Ok(Blog::default());
+        };
+
+        // Slug -> file for this language, preferring the translation
+        let mut files = BTreeMap::new();
+        for filename in filenames {
+            let Some(stem) = filename.strip_suffix(".md") else {
+                continue;
+            };
+            match stem.rsplit_once('.').and_then(|(slug, code)| Some((slug, Locale::from_code(code)?))) {
+                Some((slug, file_locale)) if file_locale == locale => {
+                    files.insert(slug.to_string(), filename);
+                }
+                Some(_) => {}
+                None => {
+                    files.entry(stem.to_string()).or_insert(filename);
+                }
+            }
+        }
+
+        let mut posts = Vec::new();
+        for (slug, filename) in files {
+            let raw = files::read_to_string(&dir.join(&filename))
+                .map_err(|_| ContentError::Missing(format!("blog/{}", filename)))?;
+            let post = parse_post(slug, &raw).map_err(|reason| ContentError::Post(filename, reason))?;
+            if SHOW_DRAFTS || !post.draft {
+                posts.push(post);
+            }
+        }
+        posts.sort_by(|a, b| b.date.cmp(&a.date).then_with(|| a.slug.cmp(&b.slug)));
+        Ok(Blog { posts })
+    }
+
+    pub fn posts(&self) -> Vec<&Post> {
+        self.posts.iter().collect()
+    }
+
+    pub fn post(&self, slug: &str) -> Option<&Post> {
+        self.posts.iter().find(|post| post.slug == slug)
+    }
+
+    /// Posts with `tag`, in any case (`Rust` finds the posts tagged `rust`)
+    pub fn tagged(&self, tag: &str) -> Vec<&Post> {
+        let tag = normalize_tag(tag);
+        self.posts.iter().filter(|post| post.tags.contains(&tag)).collect()
+    }
+
+    /// Every tag with its number of posts, alphabetically
+    pub fn tags(&self) -> Vec<(&str, usize)> {
+        let mut counts = BTreeMap::new();
+        for tag in self.posts.iter().flat_map(|post| &post.tags) {
+            *counts.entry(tag.as_str()).or_insert(0) += 1;
+        }
+        counts.into_iter().collect()
+    }
+}
+
+/// Spelling of a tag in posts and URLs: lowercase, with spaces turned into hyphens
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim().to_lowercase().replace(' ', "-")
+}
+
+/// Page `number` (from 1) of `posts`; None past the last page. An empty list has one empty page.
+pub fn paginate<'a>(posts: &'a [&'a Post], number: usize) -> Option<PostPage<'a>> {
+    let total_pages = posts.len().div_ceil(POSTS_PER_PAGE).max(1);
+    if number == 0 || number > total_pages {
+        return None;
+    }
+    let start = (number - 1) * POSTS_PER_PAGE;
+    let end = (start + POSTS_PER_PAGE).min(posts.len());
+    Some(PostPage {
+        posts: &posts[start..end],
+        number,
+        total_pages,
+    })
+}
+
+fn parse_post(slug: String, raw: &str) -> Result<Post, String> {
+    let rest = raw
+        .trim_start_matches('\u{feff}')
+        .strip_prefix("+++")
+        .ok_or("missing +++ front matter")?;
+    let (front_matter, body) = rest.split_once("\n+++").ok_or("front matter is not closed with +++")?;
+    let meta: FrontMatter = toml::from_str(front_matter).map_err(|err| err.to_string())?;
+    // A TOML date such as `2024-11-20`; a time of day is ignored
+    let date = meta
+        .date
+        .date
+        .and_then(|date| NaiveDate::from_ymd_opt(date.year.into(), date.month.into(), date.day.into()))
+        .ok_or("date must be a day such as 2024-11-20")?;
+
+    Ok(Post {
+        slug,
+        title: meta.title,
+        date,
+        summary: meta.summary,
+        tags: meta
+            .tags
+            .iter()
+            .map(|tag| normalize_tag(tag))
+            .collect(),
+        draft: meta.draft,
+        html: render_markdown(body),
+        text: plain_text(body),
+    })
+}
+
+fn render_markdown(markdown: &str) -> String {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS;
+
+    // Fenced code blocks are collected and replaced with highlighted HTML
+    let mut events = Vec::new();
+    let mut code: Option<(String, String)> = None;
+    for event in Parser::new_ext(markdown, options) {
+        match (event, code.as_mut()) {
+            (Event::Start(Tag::CodeBlock(kind)), None) => {
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => info.split([' ', ',']).next().unwrap_or("").to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                code = Some((language, String::new()));
+            }
+            (Event::Text(text), Some((_, buffer))) => buffer.push_str(&text),
+            (Event::End(TagEnd::CodeBlock), Some(_)) => {
+                let (language, buffer) = code.take().unwrap_or_default();
+                events.push(Event::Html(highlight(&language, &buffer).into()));
+            }
+            (event, _) => events.push(event),
+        }
+    }
+
+    let mut output = String::new();
+    html::push_html(&mut output, events.into_iter());
+    output
+}
+
+fn plain_text(markdown: &str) -> String {
+    let mut text = String::new();
+    let mut in_code_block = false;
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(TagEnd::CodeBlock) => in_code_block = false,
+            Event::Text(chunk) | Event::Code(chunk) if !in_code_block => text.push_str(&chunk),
+            // Keeps the words of consecutive paragraphs, items and headings apart
+            Event::End(TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::Item | TagEnd::TableCell)
+            | Event::SoftBreak
+            | Event::HardBreak => text.push(' '),
+            _ => {}
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn highlight(language: &str, code: &str) -> String {
+    let syntax = (!language.is_empty())
+        .then(|| SYNTAXES.find_syntax_by_token(language))
+        .flatten();
+    match (syntax, THEMES.themes.get(CODE_THEME)) {
+        (Some(syntax), Some(theme)) => match highlighted_html_for_string(code, &SYNTAXES, syntax, theme) {
+            Ok(html) => html,
+            Err(_) => plain_code_block(code),
+        },
+        _ => plain_code_block(code),
+    }
+}
+
+fn plain_code_block(code: &str) -> String {
+    maud::html! { pre { code { (code) } } }.into_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post(slug: &str, tags: &[&str]) -> Post {
+        Post {
+            slug: slug.to_string(),
+            title: slug.to_string(),
+            date: NaiveDate::from_ymd_opt(2024, 11, 20).unwrap(),
+            summary: String::new(),
+            tags: tags.iter().map(|tag| normalize_tag(tag)).collect(),
+            draft: false,
+            html: String::new(),
+            text: String::new(),
+        }
+    }
+
+    #[test]
+    fn tags_match_in_any_case() {
+        let blog = Blog {
+            posts: vec![post("a", &["Rust", "Web Apps"]), post("b", &["htmx"])],
+        };
+        let slugs = |tag: &str| blog.tagged(tag).iter().map(|post| post.slug.as_str()).collect::<Vec<_>>();
+        assert_eq!(slugs("rust"), ["a"]);
+        assert_eq!(slugs("Rust"), ["a"]);
+        assert_eq!(slugs("RUST"), ["a"]);
+        assert_eq!(slugs("web-apps"), ["a"]);
+        assert_eq!(slugs("Web Apps"), ["a"]);
+        assert!(slugs("rus").is_empty());
+        assert_eq!(blog.tags(), [("htmx", 1), ("rust", 1), ("web-apps", 1)]);
+    }
+}
@@ -0,0 +1,235 @@
+use axum::{
+    body::{Body, to_bytes},
+    extract::Request,
+    http::{
+        HeaderMap, HeaderValue, Method, StatusCode,
+        header::{CACHE_CONTROL, CONTENT_LENGTH, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use std::time::SystemTime;
+
+// Conditional GET for everything the server renders: pages (full and htmx partials),
+// JSON-LD, feeds and sitemaps. The layer sits outside the compression layer, so the
+// tag is computed from the bytes actually sent and each encoding gets its own strong
+// ETag; the Vary headers (htmx, language, encoding) are kept on 304 responses so
+// caches store partials and full pages as separate variants.
+
+/// Limit for buffering a response to hash it; rendered pages are nowhere near it
+const MAX_TAGGED_BYTES: usize = 8 * 1024 * 1024;
+
+/// Middleware: tags rendered responses and answers 304 when the client already has them
+pub async fn conditional_get(request: Request, next: Next) -> Response {
+    if request.method() != Method::GET {
+        return next.run(request).await;
+    }
+    let if_none_match = request.headers().get(IF_NONE_MATCH).cloned();
+    let if_modified_since = request.headers().get(IF_MODIFIED_SINCE).cloned();
+
+    let response = next.run(request).await;
+    if response.status() != StatusCode::OK || response.headers().contains_key(ETAG) || !is_rendered(response.headers()) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, MAX_TAGGED_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            tracing::error!(error = %err, "Failed to buffer response for its ETag");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let etag = etag(&Sha256::digest(&bytes), false);
+    parts.headers.insert(ETAG, etag.clone());
+    // Stored, but checked with the server before every use
+    if !parts.headers.contains_key(CACHE_CONTROL) {
+        parts.headers.insert(CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    }
+
+    // If-Modified-Since only counts when the client sent no ETag (RFC 9110, 13.2.2)
+    let not_modified = match if_none_match {
+        Some(header) => matches_any(&header, &etag),
+        None => if_modified_since.is_some_and(|since| unmodified_since(&parts.headers, &since)),
+    };
+    if not_modified {
+        // Same headers, Vary and Set-Cookie included, without the body
+        parts.status = StatusCode::NOT_MODIFIED;
+        parts.headers.remove(CONTENT_LENGTH);
+        return Response::from_parts(parts, Body::empty());
+    }
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+/// `Last-Modified` value for `time`
+pub fn http_date(time: SystemTime) -> HeaderValue {
+    let date = DateTime::<Utc>::from(time).format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+    HeaderValue::from_str(&date).expect("HTTP dates are valid header values")
+}
+
+// HTML, JSON and XML; static assets have Last-Modified from the file service instead
+fn is_rendered(headers: &HeaderMap) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/html") || v.contains("json") || v.contains("xml"))
+}
+
+/// ETag from a SHA-256 digest of the body
+pub fn etag(digest: &[u8], weak: bool) -> HeaderValue {
+    let tag = format!("{}\"{}\"", if weak { "W/" } else { "" }, URL_SAFE_NO_PAD.encode(&digest[..16]));
+    HeaderValue::from_str(&tag).expect("base64 is a valid header value")
+}
+
+/// `*` or a list of tags; If-None-Match uses the weak comparison, so `W/` is ignored
+pub fn matches_any(header: &HeaderValue, etag: &HeaderValue) -> bool {
+    let Ok(header) = header.to_str() else {
+        return false;
+    };
+    header.trim() == "*"
+        || header
+            .split(',')
+            .map(|tag| tag.trim().trim_start_matches("W/"))
+            .any(|tag| tag.as_bytes() == etag.as_bytes().strip_prefix(b"W/").unwrap_or(etag.as_bytes()))
+}
+
+/// Whether the `Last-Modified` of a response is not later than `since`
+pub fn unmodified_since(headers: &HeaderMap, since: &HeaderValue) -> bool {
+    let parse = |value: &HeaderValue| DateTime::parse_from_rfc2822(value.to_str().ok()?).ok();
+    match (headers.get(LAST_MODIFIED).and_then(parse), parse(since)) {
+        (Some(modified), Some(since)) => modified <= since,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, middleware, response::Html, routing::get};
+    use std::{future::poll_fn, time::Duration};
+    use tower::Service;
+
+    fn value(value: &str) -> HeaderValue {
+        HeaderValue::from_str(value).unwrap()
+    }
+
+    fn app() -> Router {
+        Router::new()
+            .route("/", get(|| async { Html("<p>page</p>") }))
+            .route("/page", get(|| async { ([(LAST_MODIFIED, "Tue, 01 Oct 2024 10:00:00 GMT")], Html("<p>page</p>")) }))
+            .route("/style.css", get(|| async { ([(CONTENT_TYPE, "text/css")], "p {}") }))
+            .layer(middleware::from_fn(conditional_get))
+    }
+
+    async fn send(app: &mut Router, request: Request) -> Response {
+        let Ok(()) = poll_fn(|cx| Service::<Request>::poll_ready(app, cx)).await;
+        let Ok(response) = app.call(request).await;
+        response
+    }
+
+    async fn get_with(app: &mut Router, path: &str, headers: &[(&str, &str)]) -> Response {
+        let mut request = Request::get(path);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        send(app, request.body(Body::empty()).unwrap()).await
+    }
+
+    #[test]
+    fn weak_comparison_ignores_w_prefix() {
+        let strong = value("\"abc\"");
+        let weak = value("W/\"abc\"");
+        assert!(matches_any(&value("\"abc\""), &strong));
+        assert!(matches_any(&value("W/\"abc\""), &strong));
+        assert!(matches_any(&value("\"abc\""), &weak));
+        assert!(matches_any(&value("W/\"abc\""), &weak));
+        assert!(!matches_any(&value("\"abd\""), &strong));
+        // The quotes are part of the tag
+        assert!(!matches_any(&value("abc"), &strong));
+    }
+
+    #[test]
+    fn matches_lists_and_wildcard() {
+        let etag = value("\"abc\"");
+        assert!(matches_any(&value("*"), &etag));
+        assert!(matches_any(&value(" * "), &etag));
+        assert!(matches_any(&value("\"xyz\", W/\"abc\""), &etag));
+        assert!(matches_any(&value("\"xyz\",\"abc\""), &etag));
+        assert!(!matches_any(&value("\"xyz\", \"uvw\""), &etag));
+        assert!(!matches_any(&value(""), &etag));
+    }
+
+    #[test]
+    fn compares_last_modified_with_since() {
+        let mut headers = HeaderMap::new();
+        assert!(!unmodified_since(&headers, &value("Tue, 01 Oct 2024 10:00:00 GMT")));
+
+        headers.insert(LAST_MODIFIED, value("Tue, 01 Oct 2024 10:00:00 GMT"));
+        assert!(unmodified_since(&headers, &value("Tue, 01 Oct 2024 10:00:00 GMT")));
+        assert!(unmodified_since(&headers, &value("Wed, 02 Oct 2024 10:00:00 GMT")));
+        assert!(!unmodified_since(&headers, &value("Tue, 01 Oct 2024 09:59:59 GMT")));
+        assert!(!unmodified_since(&headers, &value("yesterday")));
+    }
+
+    #[test]
+    fn http_date_round_trips() {
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_727_776_800);
+        assert_eq!(http_date(time), "Tue, 01 Oct 2024 10:00:00 GMT");
+    }
+
+    #[tokio::test]
+    async fn tags_rendered_responses() {
+        let mut app = app();
+        let response = get_with(&mut app, "/", &[]).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CACHE_CONTROL], "no-cache");
+        let etag = response.headers()[ETAG].to_str().unwrap();
+        assert!(etag.starts_with('"'), "strong tag: {}", etag);
+
+        // Static files are tagged by the file service
+        let response = get_with(&mut app, "/style.css", &[]).await;
+        assert!(!response.headers().contains_key(ETAG));
+    }
+
+    #[tokio::test]
+    async fn answers_304_without_body() {
+        let mut app = app();
+        let etag = get_with(&mut app, "/", &[]).await.headers()[ETAG].clone();
+
+        let response = get_with(&mut app, "/", &[("if-none-match", etag.to_str().unwrap())]).await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[ETAG], etag);
+        assert_eq!(response.headers()[CACHE_CONTROL], "no-cache");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(body.is_empty());
+
+        let response = get_with(&mut app, "/", &[("if-none-match", "\"other\"")]).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body, "<p>page</p>");
+    }
+
+    #[tokio::test]
+    async fn if_modified_since_only_without_if_none_match() {
+        let mut app = app();
+        let since = ("if-modified-since", "Wed, 02 Oct 2024 10:00:00 GMT");
+        let response = get_with(&mut app, "/page", &[since]).await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        let response = get_with(&mut app, "/page", &[since, ("if-none-match", "\"other\"")]).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn other_methods_pass_through() {
+        let mut app = app();
+        let request = Request::head("/").header("if-none-match", "*").body(Body::empty()).unwrap();
+        let response = send(&mut app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(!response.headers().contains_key(ETAG));
+    }
+}
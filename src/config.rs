@@ -0,0 +1,532 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::Deserialize;
+use std::{
+    convert::Infallible,
+    fmt,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+// Runtime configuration: built-in defaults, overridden by an optional TOML
+// file, then by environment variables, then by command line flags
+
+/// Loaded when present and no other file is given
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable, colored output for development
+    Pretty,
+    /// One JSON object per line, for log collectors
+    Json,
+}
+
+/// How the SMTP connection is secured
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpTls {
+    /// Plain connection upgraded with STARTTLS
+    #[default]
+    Starttls,
+    /// TLS from the start (usually port 465)
+    Tls,
+    /// No encryption, for local mock servers such as MailHog
+    None,
+}
+
+/// A password or key: `Debug` shows it redacted, so it can't end up in logs
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[redacted]")
+    }
+}
+
+impl std::str::FromStr for Secret {
+    type Err = Infallible;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(Secret(value.to_string()))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub host: IpAddr,
+    pub port: u16,
+    /// Public address of the site without a trailing slash, used for absolute
+    /// links (canonical URL, Open Graph, sitemap, JSON-LD)
+    pub base_url: String,
+    /// Site owner, shown in titles and metadata
+    pub site_name: String,
+    pub static_dir: PathBuf,
+    pub data_dir: PathBuf,
+    /// Generated files that can be deleted at any time (Open Graph images)
+    pub cache_dir: PathBuf,
+    pub log_level: LogLevel,
+    pub log_format: LogFormat,
+    /// How long open requests may take to finish after SIGTERM/SIGINT
+    pub shutdown_timeout: Duration,
+    /// Reverse proxies whose `X-Forwarded-For`/`Forwarded` headers name the client; requests
+    /// from any other address are attributed to the address itself (see `rate_limit::client_ip`)
+    pub trusted_proxies: Vec<IpRange>,
+    /// Signs the contact form and CSRF tokens; without it a random key is used, and forms
+    /// opened before a restart are rejected
+    pub secret_key: Option<Secret>,
+    /// SQLite database of contact messages
+    pub messages_db: PathBuf,
+    pub admin_user: String,
+    /// Without it the admin pages are disabled
+    pub admin_password: Option<Secret>,
+    /// Where contact messages are delivered
+    pub notify: NotifyConfig,
+}
+
+/// Delivery of contact messages (see `notifier::Notifiers`)
+#[derive(Debug, Clone, Default)]
+pub struct NotifyConfig {
+    /// SMTP relay; messages are emailed when it is set
+    pub smtp_host: Option<String>,
+    pub smtp_port: Option<u16>,
+    pub smtp_user: Option<String>,
+    pub smtp_password: Option<Secret>,
+    pub smtp_tls: SmtpTls,
+    /// Sender address, the recipient when missing
+    pub smtp_from: Option<String>,
+    /// Recipient of the emails, required with `smtp_host`
+    pub notify_email: Option<String>,
+    /// Receives each message as a JSON POST; such URLs often embed a token
+    pub notify_webhook_url: Option<Secret>,
+    /// File messages are appended to (development)
+    pub notify_log_file: Option<PathBuf>,
+}
+
+/// An address or a network in CIDR notation: `10.0.0.1`, `172.16.0.0/12`, `fd00::/8`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct IpRange {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl IpRange {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        // IPv4 clients of a dual-stack socket show up as ::ffff:a.b.c.d
+        let (network, ip, bits) = match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => (u128::from(u32::from(network)), u128::from(u32::from(ip)), 32),
+            (IpAddr::V6(network), IpAddr::V6(ip)) => (u128::from(network), u128::from(ip), 128),
+            _ => return false,
+        };
+        self.prefix == 0 || (network ^ ip) >> (bits - u32::from(self.prefix)) == 0
+    }
+}
+
+impl std::str::FromStr for IpRange {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid address or network: {}", value);
+        let (address, prefix) = match value.trim().split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix)),
+            None => (value.trim(), None),
+        };
+        let network: IpAddr = address.parse().map_err(|_| invalid())?;
+        let bits = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix.parse().ok().filter(|prefix| *prefix <= bits).ok_or_else(invalid)?,
+            None => bits,
+        };
+        Ok(IpRange { network, prefix })
+    }
+}
+
+impl TryFrom<String> for IpRange {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+#[derive(Debug, Clone, Default, Subcommand)]
+pub enum Command {
+    /// Run the web server (the default)
+    #[default]
+    Serve,
+    /// Exit successfully if the local server answers /healthz (for container health checks)
+    Healthcheck,
+    /// Prerender the site to static files for any file server; the contact form,
+    /// search and admin pages still need the running server behind it
+    Export {
+        /// Output directory, created if missing
+        #[arg(long, default_value = "dist")]
+        out: PathBuf,
+    },
+}
+
+/// Every setting is optional here; missing ones fall back to the previous layer
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    host: Option<IpAddr>,
+    port: Option<u16>,
+    base_url: Option<String>,
+    site_name: Option<String>,
+    static_dir: Option<PathBuf>,
+    data_dir: Option<PathBuf>,
+    cache_dir: Option<PathBuf>,
+    log_level: Option<LogLevel>,
+    log_format: Option<LogFormat>,
+    shutdown_timeout: Option<u64>,
+    trusted_proxies: Option<Vec<IpRange>>,
+    secret_key: Option<Secret>,
+    messages_db: Option<PathBuf>,
+    admin_user: Option<String>,
+    admin_password: Option<Secret>,
+    smtp_host: Option<String>,
+    smtp_port: Option<u16>,
+    smtp_user: Option<String>,
+    smtp_password: Option<Secret>,
+    smtp_tls: Option<SmtpTls>,
+    smtp_from: Option<String>,
+    notify_email: Option<String>,
+    notify_webhook_url: Option<Secret>,
+    notify_log_file: Option<PathBuf>,
+}
+
+/// Command line flags, each of which can also be set through its environment variable
+#[derive(Debug, Parser)]
+#[command(version, about = "Personal portfolio web server")]
+struct Cli {
+    /// TOML configuration file [default: config.toml if present]
+    #[arg(short, long, global = true, env = "CONFIG_FILE")]
+    config: Option<PathBuf>,
+
+    /// Address to bind [default: 127.0.0.1 in debug builds, 0.0.0.0 in release builds]
+    #[arg(long, global = true, env = "HOST")]
+    host: Option<IpAddr>,
+
+    /// Port to listen on [default: 3000]
+    #[arg(short, long, global = true, env = "PORT")]
+    port: Option<u16>,
+
+    /// Public URL of the site, e.g. https://example.com
+    #[arg(long, global = true, env = "BASE_URL")]
+    base_url: Option<String>,
+
+    /// Site owner name used in titles and metadata
+    #[arg(long, global = true, env = "SITE_NAME")]
+    site_name: Option<String>,
+
+    /// Directory served under /static
+    #[arg(long, global = true, env = "STATIC_DIR")]
+    static_dir: Option<PathBuf>,
+
+    /// Directory with the content and JSON-LD files [default: <static dir>/data]
+    #[arg(long, global = true, env = "DATA_DIR")]
+    data_dir: Option<PathBuf>,
+
+    /// Directory for generated files such as Open Graph images [default: cache]
+    #[arg(long, global = true, env = "CACHE_DIR")]
+    cache_dir: Option<PathBuf>,
+
+    /// Logging verbosity
+    #[arg(long, global = true, env = "LOG_LEVEL")]
+    log_level: Option<LogLevel>,
+
+    /// Log output format [default: pretty in debug builds, json in release builds]
+    #[arg(long, global = true, env = "LOG_FORMAT")]
+    log_format: Option<LogFormat>,
+
+    /// Seconds to wait for open requests on shutdown [default: 8]
+    #[arg(long, global = true, env = "SHUTDOWN_TIMEOUT")]
+    shutdown_timeout: Option<u64>,
+
+    /// Comma-separated addresses or networks of reverse proxies allowed to set the client
+    /// address in X-Forwarded-For or Forwarded [default: none, the peer address is used]
+    #[arg(long, global = true, env = "TRUSTED_PROXIES", value_delimiter = ',')]
+    trusted_proxies: Option<Vec<IpRange>>,
+
+    /// Key for signing form and CSRF tokens [default: random on every start]
+    #[arg(long, global = true, env = "SECRET_KEY", hide_env_values = true)]
+    secret_key: Option<Secret>,
+
+    /// SQLite database for contact messages [default: messages.db]
+    #[arg(long, global = true, env = "MESSAGES_DB")]
+    messages_db: Option<PathBuf>,
+
+    /// User name for the admin pages [default: admin]
+    #[arg(long, global = true, env = "ADMIN_USER")]
+    admin_user: Option<String>,
+
+    /// Password for the admin pages, which are disabled without one
+    #[arg(long, global = true, env = "ADMIN_PASSWORD", hide_env_values = true)]
+    admin_password: Option<Secret>,
+
+    /// SMTP relay for emailing contact messages
+    #[arg(long, global = true, env = "SMTP_HOST")]
+    smtp_host: Option<String>,
+
+    /// SMTP port [default: 587 with starttls, 465 with tls, 25 with none]
+    #[arg(long, global = true, env = "SMTP_PORT")]
+    smtp_port: Option<u16>,
+
+    /// SMTP user, used together with the password
+    #[arg(long, global = true, env = "SMTP_USER")]
+    smtp_user: Option<String>,
+
+    #[arg(long, global = true, env = "SMTP_PASSWORD", hide_env_values = true)]
+    smtp_password: Option<Secret>,
+
+    /// How the SMTP connection is secured [default: starttls]
+    #[arg(long, global = true, env = "SMTP_TLS")]
+    smtp_tls: Option<SmtpTls>,
+
+    /// Sender of the emails, e.g. "Web <web@example.com>" [default: the recipient]
+    #[arg(long, global = true, env = "SMTP_FROM")]
+    smtp_from: Option<String>,
+
+    /// Recipient of the emails, required with --smtp-host
+    #[arg(long, global = true, env = "NOTIFY_EMAIL")]
+    notify_email: Option<String>,
+
+    /// URL that receives each contact message as a JSON POST
+    #[arg(long, global = true, env = "NOTIFY_WEBHOOK_URL", hide_env_values = true)]
+    notify_webhook_url: Option<Secret>,
+
+    /// File contact messages are appended to [default: stdout without SMTP]
+    #[arg(long, global = true, env = "NOTIFY_LOG_FILE")]
+    notify_log_file: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(PathBuf, std::io::Error),
+    Parse(PathBuf, toml::de::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read(path, err) => write!(f, "cannot read {}: {}", path.display(), err),
+            ConfigError::Parse(path, err) => write!(f, "invalid config file {}: {}", path.display(), err),
+            ConfigError::Invalid(reason) => write!(f, "invalid configuration: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// Build the configuration from the command line, environment and config file,
+    /// along with the subcommand to run
+    pub fn load() -> Result<(Self, Command), ConfigError> {
+        let cli = Cli::parse();
+        let file = read_config_file(cli.config.as_deref())?;
+
+        let static_dir = cli.static_dir.or(file.static_dir).unwrap_or_else(default_static_dir);
+        let data_dir = cli
+            .data_dir
+            .or(file.data_dir)
+            .unwrap_or_else(|| static_dir.join("data"));
+
+        let port = cli.port.or(file.port).unwrap_or(3000);
+        let base_url = cli
+            .base_url
+            .or(file.base_url)
+            .unwrap_or_else(|| format!("http://localhost:{}", port));
+        if !base_url.starts_with("http://") && !base_url.starts_with("https://") {
+            return Err(ConfigError::Invalid(format!(
+                "base_url must start with http:// or https://, got {}",
+                base_url
+            )));
+        }
+
+        let config = Config {
+            host: cli.host.or(file.host).unwrap_or_else(default_host),
+            port,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            site_name: cli
+                .site_name
+                .or(file.site_name)
+                .unwrap_or_else(|| "Tu Nombre".to_string()),
+            static_dir,
+            data_dir,
+            cache_dir: cli.cache_dir.or(file.cache_dir).unwrap_or_else(|| PathBuf::from("cache")),
+            log_level: cli.log_level.or(file.log_level).unwrap_or_else(default_log_level),
+            log_format: cli.log_format.or(file.log_format).unwrap_or_else(default_log_format),
+            // Leaves time before Docker's default 10s stop timeout sends SIGKILL
+            shutdown_timeout: Duration::from_secs(cli.shutdown_timeout.or(file.shutdown_timeout).unwrap_or(8)),
+            trusted_proxies: cli.trusted_proxies.or(file.trusted_proxies).unwrap_or_default(),
+            // Empty counts as unset, so a blank variable doesn't become a key or password
+            secret_key: cli.secret_key.or(file.secret_key).filter(|key| !key.expose().is_empty()),
+            messages_db: cli.messages_db.or(file.messages_db).unwrap_or_else(|| PathBuf::from("messages.db")),
+            admin_user: cli.admin_user.or(file.admin_user).unwrap_or_else(|| "admin".to_string()),
+            admin_password: cli
+                .admin_password
+                .or(file.admin_password)
+                .filter(|password| !password.expose().is_empty()),
+            notify: NotifyConfig {
+                smtp_host: cli.smtp_host.or(file.smtp_host),
+                smtp_port: cli.smtp_port.or(file.smtp_port),
+                smtp_user: cli.smtp_user.or(file.smtp_user),
+                smtp_password: cli.smtp_password.or(file.smtp_password),
+                smtp_tls: cli.smtp_tls.or(file.smtp_tls).unwrap_or_default(),
+                smtp_from: cli.smtp_from.or(file.smtp_from),
+                notify_email: cli.notify_email.or(file.notify_email),
+                notify_webhook_url: cli.notify_webhook_url.or(file.notify_webhook_url),
+                notify_log_file: cli.notify_log_file.or(file.notify_log_file),
+            },
+        };
+        Ok((config, cli.command.unwrap_or_default()))
+    }
+
+    pub fn bind_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.host, self.port)
+    }
+
+    /// Absolute URL for a path on this site
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// Fill in the `{{base_url}}` and `{{site_name}}` placeholders used in
+    /// robots.txt and sitemap.xml
+    pub fn expand(&self, template: &str) -> String {
+        template
+            .replace("{{base_url}}", &self.base_url)
+            .replace("{{site_name}}", &self.site_name)
+    }
+
+    /// Same as [`Config::expand`] for placeholders inside JSON strings (the JSON-LD files)
+    pub fn expand_json(&self, template: &str) -> String {
+        template
+            .replace("{{base_url}}", &json_escape(&self.base_url))
+            .replace("{{site_name}}", &json_escape(&self.site_name))
+    }
+}
+
+// String contents as they appear between the quotes of a JSON string
+fn json_escape(value: &str) -> String {
+    let quoted = serde_json::Value::from(value).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+fn read_config_file(path: Option<&Path>) -> Result<ConfigFile, ConfigError> {
+    // An explicitly requested file must exist, the default one is optional
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None if Path::new(DEFAULT_CONFIG_FILE).is_file() => PathBuf::from(DEFAULT_CONFIG_FILE),
+        None => return Ok(ConfigFile::default()),
+    };
+
+    let raw = std::fs::read_to_string(&path).map_err(|err| ConfigError::Read(path.clone(), err))?;
+    toml::from_str(&raw).map_err(|err| ConfigError::Parse(path, err))
+}
+
+// Development: localhost only. Production: all interfaces
+fn default_host() -> IpAddr {
+    if cfg!(debug_assertions) {
+        IpAddr::V4(Ipv4Addr::LOCALHOST)
+    } else {
+        IpAddr::V4(Ipv4Addr::UNSPECIFIED)
+    }
+}
+
+fn default_log_level() -> LogLevel {
+    if cfg!(debug_assertions) {
+        LogLevel::Debug
+    } else {
+        LogLevel::Info
+    }
+}
+
+fn default_log_format() -> LogFormat {
+    if cfg!(debug_assertions) {
+        LogFormat::Pretty
+    } else {
+        LogFormat::Json
+    }
+}
+
+// Try multiple possible locations for the static directory
+fn default_static_dir() -> PathBuf {
+    let possible_paths = [
+        "static",  // Local development
+        "/static", // Docker container
+    ];
+
+    possible_paths
+        .iter()
+        .map(PathBuf::from)
+        .find(|path| path.is_dir())
+        .unwrap_or_else(|| PathBuf::from("static"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secrets_are_redacted_from_debug() {
+        let notify = NotifyConfig {
+            smtp_password: Some(Secret("hunter2".into())),
+            notify_webhook_url: Some(Secret("https://hooks.example.com/T0/abc".into())),
+            ..Default::default()
+        };
+        let output = format!("{:?}", notify);
+        assert!(!output.contains("hunter2"));
+        assert!(!output.contains("hooks.example.com"));
+        assert!(output.contains("[redacted]"));
+    }
+
+    #[test]
+    fn file_settings_parse() {
+        let file: ConfigFile = toml::from_str(
+            "secret_key = \"s3cret\"\nadmin_password = \"pw\"\nsmtp_host = \"mail.example.com\"\nsmtp_tls = \"tls\"\n",
+        )
+        .unwrap();
+        assert_eq!(file.secret_key.unwrap().expose(), "s3cret");
+        assert_eq!(file.admin_password.unwrap().expose(), "pw");
+        assert_eq!(file.smtp_host.as_deref(), Some("mail.example.com"));
+        assert_eq!(file.smtp_tls, Some(SmtpTls::Tls));
+    }
+}
@@ -0,0 +1,402 @@
+use crate::blog::Blog;
+use crate::files;
+use crate::i18n::Locale;
+use crate::routes::Route;
+use crate::search::SearchIndex;
+use chrono::NaiveDate;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, RwLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, SystemTime},
+};
+
+// Site content loaded from the JSON files in the data directory (static/data by default).
+// `profile.json` holds the default language; `profile.en.json`, when present, replaces it
+// for English, and the same goes for every other data file.
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    pub title: String,
+    pub summary: String,
+    pub bio: Vec<String>,
+    pub avatar: String,
+    pub contact: ContactDetails,
+    pub pitch: String,
+    pub strengths: Vec<String>,
+    pub availability: Vec<Availability>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ContactDetails {
+    pub email: String,
+    pub phone: String,
+    pub location: String,
+    pub linkedin: String,
+    pub github: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Availability {
+    /// Suffix for the `availability-item-*` CSS class (available, consultancy, mentoring)
+    pub kind: String,
+    pub title: String,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SkillCategory {
+    pub name: String,
+    pub skills: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Experience {
+    pub jobs: Vec<Job>,
+    pub education: Vec<Education>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Job {
+    pub period: String,
+    pub title: String,
+    pub company: String,
+    pub summary: String,
+    pub highlights: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Education {
+    pub title: String,
+    pub institution: String,
+    pub period: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Portfolio {
+    pub projects: Vec<Project>,
+    pub open_source: Vec<OpenSourceContribution>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Project {
+    pub slug: String,
+    pub icon: String,
+    pub label: String,
+    pub title: String,
+    pub description: String,
+    /// Tech stack
+    pub tags: Vec<String>,
+    /// Paragraphs of the detail page; `description` is shown when empty
+    #[serde(default)]
+    pub details: Vec<String>,
+    #[serde(default)]
+    pub screenshots: Vec<Screenshot>,
+    pub role: Option<String>,
+    #[serde(default)]
+    pub outcomes: Vec<String>,
+    /// External URLs behind /projects/demo/{slug} and /projects/code/{slug}
+    pub demo_url: Option<String>,
+    pub repository_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Screenshot {
+    /// Image URL, usually under /static
+    pub src: String,
+    pub alt: String,
+}
+
+impl Portfolio {
+    pub fn project(&self, slug: &str) -> Option<&Project> {
+        self.projects.iter().find(|project| project.slug == slug)
+    }
+
+    /// Projects with `tag` whose text contains every word of `query`, ignoring case
+    pub fn filter(&self, tag: Option<&str>, query: Option<&str>) -> Vec<&Project> {
+        let words: Vec<String> = query.unwrap_or_default().split_whitespace().map(str::to_lowercase).collect();
+        self.projects
+            .iter()
+            .filter(|project| tag.is_none_or(|tag| project.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))))
+            .filter(|project| {
+                let text = project.search_text();
+                words.iter().all(|word| text.contains(word.as_str()))
+            })
+            .collect()
+    }
+
+    /// Every tag with its number of projects, alphabetically
+    pub fn tags(&self) -> Vec<(&str, usize)> {
+        let mut counts = BTreeMap::new();
+        for tag in self.projects.iter().flat_map(|project| &project.tags) {
+            *counts.entry(tag.as_str()).or_insert(0) += 1;
+        }
+        counts.into_iter().collect()
+    }
+}
+
+impl Project {
+    // Lowercase text matched by the search box
+    fn search_text(&self) -> String {
+        [&self.label, &self.title, &self.description]
+            .into_iter()
+            .chain(&self.tags)
+            .chain(&self.details)
+            .chain(&self.role)
+            .chain(&self.outcomes)
+            .map(|text| text.to_lowercase())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OpenSourceContribution {
+    pub name: String,
+    pub description: String,
+}
+
+/// Dated updates published in the RSS and Atom feeds, next to the blog posts
+#[derive(Debug, Clone, Deserialize)]
+pub struct Feed {
+    pub entries: Vec<FeedEntry>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FeedEntry {
+    /// Stable identifier; changing it makes feed readers show the entry again
+    pub id: String,
+    pub kind: FeedKind,
+    pub date: NaiveDate,
+    /// Last change to the entry, when different from `date`
+    pub updated: Option<NaiveDate>,
+    pub title: String,
+    pub summary: String,
+    /// Page on this site, without the language prefix; defaults to the section of `kind`
+    pub link: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedKind {
+    Project,
+    Experience,
+    Announcement,
+}
+
+impl FeedEntry {
+    pub fn path(&self) -> Cow<'_, str> {
+        match &self.link {
+            Some(link) => link.into(),
+            None => match self.kind {
+                FeedKind::Project => Route::Projects.path(),
+                FeedKind::Experience => Route::Experience.path(),
+                FeedKind::Announcement => Route::Home.path(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Content {
+    pub profile: Profile,
+    pub skills: Vec<SkillCategory>,
+    pub experience: Experience,
+    pub portfolio: Portfolio,
+    pub blog: Blog,
+    pub feed: Feed,
+    /// Raw JSON-LD documents embedded in every page head
+    pub website_json_ld: String,
+    pub person_json_ld: String,
+    /// Built from the fields above
+    pub search: SearchIndex,
+    /// When this version was loaded, the `Last-Modified` of the JSON-LD documents
+    pub loaded_at: SystemTime,
+}
+
+#[derive(Debug)]
+pub enum ContentError {
+    Missing(String),
+    Parse(String, serde_json::Error),
+    Post(String, String),
+}
+
+impl fmt::Display for ContentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContentError::Missing(file) => write!(f, "data file {} not found", file),
+            ContentError::Parse(file, err) => write!(f, "invalid data file {}: {}", file, err),
+            ContentError::Post(file, reason) => write!(f, "invalid blog post {}: {}", file, reason),
+        }
+    }
+}
+
+impl std::error::Error for ContentError {}
+
+impl Content {
+    pub fn load(dir: &Path, locale: Locale) -> Result<Self, ContentError> {
+        let mut content = Content {
+            profile: load_json(dir, "profile.json", locale)?,
+            skills: load_json(dir, "skills.json", locale)?,
+            experience: load_json(dir, "experience.json", locale)?,
+            portfolio: load_json(dir, "projects.json", locale)?,
+            blog: Blog::load(dir, locale)?,
+            feed: load_json(dir, "feed.json", locale)?,
+            website_json_ld: load_json_ld(dir, "website.json", locale)?,
+            person_json_ld: load_json_ld(dir, "person.json", locale)?,
+            search: SearchIndex::empty(locale),
+            loaded_at: SystemTime::now(),
+        };
+        content.search = SearchIndex::build(&content, locale);
+        Ok(content)
+    }
+
+    /// Content for every language
+    pub fn load_all(dir: &Path) -> Result<HashMap<Locale, Arc<Content>>, ContentError> {
+        Locale::ALL
+            .into_iter()
+            .map(|locale| Ok((locale, Arc::new(Content::load(dir, locale)?))))
+            .collect()
+    }
+}
+
+/// Holds the current content and swaps in new versions when the data files change.
+///
+/// Readers get an `Arc<Content>` snapshot, so a reload never affects a render in progress.
+/// When a reload fails the last good version keeps being served.
+pub struct ContentStore {
+    dir: PathBuf,
+    current: RwLock<HashMap<Locale, Arc<Content>>>,
+    version: AtomicU64,
+    /// Why the latest reload failed, cleared by the next successful one
+    reload_error: RwLock<Option<String>>,
+}
+
+impl ContentStore {
+    pub fn new(dir: PathBuf, content: HashMap<Locale, Arc<Content>>) -> Self {
+        ContentStore {
+            dir,
+            current: RwLock::new(content),
+            version: AtomicU64::new(1),
+            reload_error: RwLock::new(None),
+        }
+    }
+
+    pub fn current(&self, locale: Locale) -> Arc<Content> {
+        let current = self.current.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+        // Every language is loaded together, the fallback is never used
+        current
+            .get(&locale)
+            .or_else(|| current.get(&Locale::DEFAULT))
+            .cloned()
+            .expect("content is loaded for the default language")
+    }
+
+    /// Incremented on every successful reload
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::Acquire)
+    }
+
+    /// Error of the latest reload, when it failed and the previous version is still served
+    pub fn reload_error(&self) -> Option<String> {
+        self.reload_error.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    pub fn reload(&self) -> Result<(), ContentError> {
+        let result = Content::load_all(&self.dir);
+        *self
+            .reload_error
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = result.as_ref().err().map(ToString::to_string);
+        *self
+            .current
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = result?;
+        self.version.fetch_add(1, Ordering::AcqRel);
+        Ok(())
+    }
+
+    /// Poll the data directory and reload whenever a file is added, removed or modified
+    pub fn watch(self: Arc<Self>, interval: Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut last_seen = fingerprint(&self.dir);
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+            loop {
+                ticker.tick().await;
+
+                let seen = fingerprint(&self.dir);
+                if seen == last_seen {
+                    continue;
+                }
+                last_seen = seen;
+
+                // Parsing is blocking file IO, keep it off the async workers
+                let store = self.clone();
+                match tokio::task::spawn_blocking(move || store.reload()).await {
+                    Ok(Ok(())) => tracing::info!(version = self.version(), "Content reloaded"),
+                    Ok(Err(err)) => tracing::error!(error = %err, "Content reload failed, keeping previous version"),
+                    Err(err) => tracing::error!(error = %err, "Content reload task failed"),
+                }
+            }
+        })
+    }
+}
+
+fn read_data_file(dir: &Path, filename: &str) -> Result<String, ContentError> {
+    files::read_to_string(&dir.join(filename)).map_err(|_| ContentError::Missing(filename.to_string()))
+}
+
+// `name.en.json` for English when it exists, `name.json` otherwise
+fn localized_filename(dir: &Path, filename: &str, locale: Locale) -> String {
+    if locale == Locale::DEFAULT {
+        return filename.to_string();
+    }
+    filename
+        .strip_suffix(".json")
+        .map(|stem| format!("{}.{}.json", stem, locale.code()))
+        .filter(|localized| files::is_file(&dir.join(localized)))
+        .unwrap_or_else(|| filename.to_string())
+}
+
+fn load_json<T: DeserializeOwned>(dir: &Path, filename: &str, locale: Locale) -> Result<T, ContentError> {
+    let filename = localized_filename(dir, filename, locale);
+    let raw = read_data_file(dir, &filename)?;
+    serde_json::from_str(&raw).map_err(|err| ContentError::Parse(filename, err))
+}
+
+// JSON-LD is embedded as-is apart from the placeholders (see `Config::expand_json`), only check that it parses
+fn load_json_ld(dir: &Path, filename: &str, locale: Locale) -> Result<String, ContentError> {
+    let filename = &localized_filename(dir, filename, locale);
+    let raw = read_data_file(dir, filename)?;
+    serde_json::from_str::<serde_json::Value>(&raw)
+        .map_err(|err| ContentError::Parse(filename.to_string(), err))?;
+    Ok(raw)
+}
+
+// Names, sizes and modification times of every file in the directory and its
+// subdirectories (blog posts)
+fn fingerprint(dir: &Path) -> Vec<(PathBuf, u64, Option<SystemTime>)> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            entries.extend(fingerprint(&entry.path()));
+        } else {
+            entries.push((entry.path(), metadata.len(), metadata.modified().ok()));
+        }
+    }
+    entries.sort();
+    entries
+}
@@ -0,0 +1,168 @@
+use crate::config::Config;
+use crate::content::Content;
+use crate::csrf::CsrfToken;
+use crate::i18n::{Lang, Locale};
+use crate::messages::MessageStatus;
+use crate::routes::Route;
+use crate::state::AppState;
+use crate::views;
+use crate::views::page::Page;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{Html, IntoResponse, Redirect, Response},
+};
+use axum_htmx::HxRequest;
+use maud::Markup;
+
+fn into_html_response(markup: Markup) -> Html<String> {
+    Html(markup.into_string())
+}
+
+fn render(
+    config: &Config,
+    site: &Content,
+    csrf: &CsrfToken,
+    lang: &Lang,
+    is_htmx: bool,
+    page: Page,
+) -> Html<String> {
+    if is_htmx {
+        into_html_response(views::layout::render_partial(config, page))
+    } else {
+        into_html_response(views::layout::render_page_with_content(config, site, csrf, lang, "admin", page))
+    }
+}
+
+fn server_error(config: &Config, locale: Locale, err: impl std::fmt::Display) -> Response {
+    tracing::error!(error = %err, "Admin inbox error");
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        into_html_response(views::error_view::server_error(config, locale)),
+    )
+        .into_response()
+}
+
+fn not_found(config: &Config, locale: Locale, is_htmx: bool) -> Response {
+    if is_htmx {
+        views::error_view::htmx_error(StatusCode::NOT_FOUND, views::error_view::not_found_content(locale))
+    } else {
+        (StatusCode::NOT_FOUND, into_html_response(views::error_view::not_found(config, locale))).into_response()
+    }
+}
+
+// After an action htmx swaps the new view in place and updates the address bar,
+// plain form posts get a redirect (post/redirect/get)
+fn after_action(
+    config: &Config,
+    site: &Content,
+    csrf: &CsrfToken,
+    lang: &Lang,
+    is_htmx: bool,
+    location: &str,
+    page: Page,
+) -> Response {
+    let location = lang.locale.path(location);
+    if is_htmx {
+        ([("hx-push-url", location)], render(config, site, csrf, lang, true, page)).into_response()
+    } else {
+        Redirect::to(&location).into_response()
+    }
+}
+
+pub async fn inbox(
+    State(state): State<AppState>,
+    HxRequest(is_htmx): HxRequest,
+    csrf: CsrfToken,
+    lang: Lang,
+) -> Response {
+    match state.messages.list().await {
+        Ok(messages) => render(
+            &state.config,
+            &state.content.current(lang.locale),
+            &csrf,
+            &lang,
+            is_htmx,
+            views::admin_view::render_inbox(&messages, lang.locale),
+        )
+        .into_response(),
+        Err(err) => server_error(&state.config, lang.locale, err),
+    }
+}
+
+pub async fn message(
+    State(state): State<AppState>,
+    HxRequest(is_htmx): HxRequest,
+    csrf: CsrfToken,
+    lang: Lang,
+    Path(id): Path<i64>,
+) -> Response {
+    match state.messages.get(id).await {
+        Ok(Some(message)) => render(
+            &state.config,
+            &state.content.current(lang.locale),
+            &csrf,
+            &lang,
+            is_htmx,
+            views::admin_view::render_message(&message, &csrf, lang.locale),
+        )
+        .into_response(),
+        Ok(None) => not_found(&state.config, lang.locale, is_htmx),
+        Err(err) => server_error(&state.config, lang.locale, err),
+    }
+}
+
+pub async fn mark_handled(
+    State(state): State<AppState>,
+    HxRequest(is_htmx): HxRequest,
+    csrf: CsrfToken,
+    lang: Lang,
+    Path(id): Path<i64>,
+) -> Response {
+    match state.messages.set_status(id, MessageStatus::Handled).await {
+        Ok(true) => {}
+        Ok(false) => return not_found(&state.config, lang.locale, is_htmx),
+        Err(err) => return server_error(&state.config, lang.locale, err),
+    }
+
+    match state.messages.get(id).await {
+        Ok(Some(message)) => after_action(
+            &state.config,
+            &state.content.current(lang.locale),
+            &csrf,
+            &lang,
+            is_htmx,
+            &views::admin_view::message_path(id),
+            views::admin_view::render_message(&message, &csrf, lang.locale),
+        ),
+        Ok(None) => not_found(&state.config, lang.locale, is_htmx),
+        Err(err) => server_error(&state.config, lang.locale, err),
+    }
+}
+
+pub async fn delete(
+    State(state): State<AppState>,
+    HxRequest(is_htmx): HxRequest,
+    csrf: CsrfToken,
+    lang: Lang,
+    Path(id): Path<i64>,
+) -> Response {
+    match state.messages.delete(id).await {
+        Ok(true) => {}
+        Ok(false) => return not_found(&state.config, lang.locale, is_htmx),
+        Err(err) => return server_error(&state.config, lang.locale, err),
+    }
+
+    match state.messages.list().await {
+        Ok(messages) => after_action(
+            &state.config,
+            &state.content.current(lang.locale),
+            &csrf,
+            &lang,
+            is_htmx,
+            &Route::AdminMessages.path(),
+            views::admin_view::render_inbox(&messages, lang.locale),
+        ),
+        Err(err) => server_error(&state.config, lang.locale, err),
+    }
+}
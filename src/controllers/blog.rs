@@ -0,0 +1,34 @@
+use crate::blog;
+use crate::generate_page_handler;
+use crate::views::blog_view;
+use axum::extract::{Path, Query};
+use serde::{Deserialize, Serialize};
+
+// `?page=2`; without it, the first page
+#[derive(Deserialize, Serialize, Default)]
+#[serde(default)]
+pub struct PageQuery {
+    page: Option<usize>,
+}
+
+generate_page_handler!(index, "blog", [query: Query<PageQuery>], |site, state, csrf, locale| {
+    let posts = site.blog.posts();
+    blog::paginate(&posts, query.page.unwrap_or(1))
+        .map(|page| blog_view::render_index(&page, &site.blog.tags(), None, locale))
+});
+
+generate_page_handler!(post, "blog", [slug: Path<String>], |site, state, csrf, locale| {
+    site.blog.post(&slug).map(|post| blog_view::render_post(post, locale))
+});
+
+// `/blog/tags/Rust` shows the `rust` posts, with the lowercase URL as canonical
+generate_page_handler!(tag, "blog", [tag: Path<String>, query: Query<PageQuery>], |site, state, csrf, locale| {
+    let tag = blog::normalize_tag(&tag);
+    let posts = site.blog.tagged(&tag);
+    if posts.is_empty() {
+        None
+    } else {
+        blog::paginate(&posts, query.page.unwrap_or(1))
+            .map(|page| blog_view::render_index(&page, &site.blog.tags(), Some(&tag), locale))
+    }
+});
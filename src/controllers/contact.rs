@@ -1,75 +1,178 @@
+use crate::csrf::CsrfToken;
 use crate::generate_page_handler;
+use crate::i18n::Lang;
+use crate::messages::NewMessage;
+use crate::rate_limit;
+use crate::spam;
+use crate::state::AppState;
+use crate::telemetry::{self, ContactOutcome};
+use crate::validation::{self, FieldErrors};
 use crate::views;
-use axum::{extract::Form, response::{Html, IntoResponse}};
+use axum::{
+    extract::{ConnectInfo, Form, State},
+    http::{HeaderMap, header::USER_AGENT},
+    response::{Html, IntoResponse},
+};
+use std::net::SocketAddr;
 use axum_htmx::HxRequest;
 use maud::Markup;
 use serde::Deserialize;
 
-#[derive(Deserialize)]
+pub const NAME_MAX_CHARS: usize = 100;
+pub const EMAIL_MAX_CHARS: usize = 254;
+pub const MESSAGE_MIN_CHARS: usize = 10;
+pub const MESSAGE_MAX_CHARS: usize = 5000;
+
+// Missing fields deserialize as empty so they get a per-field error instead of a 422
+#[derive(Deserialize, Default, Clone)]
+#[serde(default)]
 pub struct ContactForm {
     pub name: String,
     pub email: String,
     pub message: String,
+    /// Honeypot, hidden from people and expected to stay empty
+    pub website: String,
+    pub form_token: String,
+}
+
+impl ContactForm {
+    /// Copy with surrounding whitespace removed, validated and used to refill the form
+    pub fn trimmed(&self) -> Self {
+        ContactForm {
+            name: self.name.trim().to_string(),
+            email: self.email.trim().to_string(),
+            message: self.message.trim().to_string(),
+            website: self.website.trim().to_string(),
+            form_token: self.form_token.trim().to_string(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), FieldErrors> {
+        let mut errors = FieldErrors::new();
+
+        validation::required(&mut errors, "name", &self.name);
+        validation::no_control_chars(&mut errors, "name", &self.name, false);
+        validation::max_chars(&mut errors, "name", &self.name, NAME_MAX_CHARS);
+
+        validation::required(&mut errors, "email", &self.email);
+        validation::max_chars(&mut errors, "email", &self.email, EMAIL_MAX_CHARS);
+        validation::email(&mut errors, "email", &self.email);
+
+        validation::required(&mut errors, "message", &self.message);
+        validation::no_control_chars(&mut errors, "message", &self.message, true);
+        validation::min_chars(&mut errors, "message", &self.message, MESSAGE_MIN_CHARS);
+        validation::max_chars(&mut errors, "message", &self.message, MESSAGE_MAX_CHARS);
+
+        errors.into_result(())
+    }
 }
 
 fn into_html_response(markup: Markup) -> Html<String> {
     Html(markup.into_string())
 }
 
-generate_page_handler!(handler, "contact", contact_view);
+// Every rendered form carries a fresh anti-spam token, so the page is never cached
+generate_page_handler!(handler, "contact", |site, state, csrf, locale| {
+    views::contact_view::render(&site, &state.form_tokens.issue(), &csrf, locale).uncached()
+});
 
 pub async fn contact_submit(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     HxRequest(is_htmx): HxRequest,
+    csrf: CsrfToken,
+    lang: Lang,
     Form(form): Form<ContactForm>,
 ) -> impl IntoResponse {
-    let name = form.name.trim();
-    let email = form.email.trim();
-    let message = form.message.trim();
-
-    if name.is_empty() || email.is_empty() || message.is_empty() {
-        let error_content = views::contact_view::render_error("Todos los campos son obligatorios");
-        return if is_htmx {
-            into_html_response(error_content)
+    let locale = lang.locale;
+    let site = state.content.current(locale);
+    let form = form.trimmed();
+    let client_ip = rate_limit::client_ip(addr.ip(), &headers, &state.config.trusted_proxies);
+
+    // htmx swaps the form block in place, plain posts get the whole contact page back
+    let respond = |form_block: Markup| {
+        if is_htmx {
+            into_html_response(form_block)
         } else {
             into_html_response(views::layout::render_page_with_content(
+                &state.config,
+                &site,
+                &csrf,
+                &lang,
                 "contact",
-                error_content,
+                views::contact_view::render_with_form(&site, form_block, locale),
             ))
-        };
+        }
+    };
+
+    // Bots fill every field; pretend it worked so they don't adapt
+    if spam::is_honeypot_filled(&form.website) {
+        tracing::info!(%client_ip, "Dropped contact submission: {} field filled", spam::HONEYPOT_FIELD);
+        telemetry::record_contact_submission(ContactOutcome::Spam, "honeypot");
+        return respond(views::contact_view::render_success(&form.name, &site, locale));
     }
 
-    if !email.contains('@') {
-        let error_content = views::contact_view::render_error("Por favor ingresa un email válido");
-        return if is_htmx {
-            into_html_response(error_content)
-        } else {
-            into_html_response(views::layout::render_page_with_content(
-                "contact",
-                error_content,
-            ))
-        };
+    if let Err(err) = state.form_tokens.verify(&form.form_token) {
+        tracing::warn!(%client_ip, reason = %err, "Rejected contact submission");
+        telemetry::record_contact_submission(ContactOutcome::Spam, err.as_str());
+        let token = state.form_tokens.issue();
+        return respond(views::contact_view::render_error(
+            locale.t("contact.token_rejected"),
+            &form,
+            &token,
+            &csrf,
+            locale,
+        ));
     }
 
-    if message.len() < 10 {
-        let error_content =
-            views::contact_view::render_error("El mensaje debe tener al menos 10 caracteres");
-        return if is_htmx {
-            into_html_response(error_content)
-        } else {
-            into_html_response(views::layout::render_page_with_content(
-                "contact",
-                error_content,
-            ))
-        };
+    // The token stays valid so the corrected form can be sent again
+    if let Err(errors) = form.validate() {
+        for field in errors.fields() {
+            telemetry::record_contact_submission(ContactOutcome::Invalid, field);
+        }
+        return respond(views::contact_view::render_form(&form, &errors, &form.form_token, &csrf, locale));
     }
 
-    let success_content = views::contact_view::render_success(&name);
-    if is_htmx {
-        into_html_response(success_content)
-    } else {
-        into_html_response(views::layout::render_page_with_content(
-            "contact",
-            success_content,
-        ))
+    if let Err(err) = state.form_tokens.consume(&form.form_token) {
+        tracing::warn!(%client_ip, reason = %err, "Rejected contact submission");
+        telemetry::record_contact_submission(ContactOutcome::Spam, err.as_str());
+        let token = state.form_tokens.issue();
+        return respond(views::contact_view::render_error(
+            locale.t("contact.token_rejected"),
+            &form,
+            &token,
+            &csrf,
+            locale,
+        ));
     }
-}
+
+    let new_message = NewMessage {
+        name: form.name.clone(),
+        email: form.email.clone(),
+        message: form.message.clone(),
+        ip: Some(client_ip.to_string()),
+        user_agent: headers
+            .get(USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string),
+    };
+
+    if let Err(err) = state.messages.insert(new_message.clone()).await {
+        tracing::error!(error = %err, "Failed to store contact message");
+        telemetry::record_contact_submission(ContactOutcome::Error, "storage");
+        return respond(views::contact_view::render_error(
+            locale.t("contact.storage_failed"),
+            &form,
+            &state.form_tokens.issue(),
+            &csrf,
+            locale,
+        ));
+    }
+
+    // Delivered in the background so mail latency doesn't delay the response
+    state.notifiers.dispatch(new_message);
+    telemetry::record_contact_submission(ContactOutcome::Success, "none");
+
+    respond(views::contact_view::render_success(&form.name, &site, locale))
+}
\ No newline at end of file
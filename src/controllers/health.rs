@@ -0,0 +1,91 @@
+use crate::config::Config;
+use crate::state::AppState;
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use serde_json::{Value, json};
+use std::{net::IpAddr, time::Duration};
+
+// Liveness and readiness probes for container orchestrators and load balancers
+
+/// The process is up and serving requests
+pub async fn healthz() -> impl IntoResponse {
+    Json(json!({
+        "status": "ok",
+        "version": env!("CARGO_PKG_VERSION"),
+    }))
+}
+
+/// Everything needed to serve traffic is available; 503 with the failing checks otherwise.
+/// The endpoint is public, so it only says which checks failed and the reasons go to the log.
+pub async fn readyz(State(state): State<AppState>) -> impl IntoResponse {
+    let mut checks = serde_json::Map::new();
+    let mut ready = true;
+    let mut record = |name: &str, result: Result<(), String>| {
+        let status = match result {
+            Ok(()) => "ok",
+            Err(error) => {
+                tracing::warn!(check = name, error = %error, "Readiness check failed");
+                ready = false;
+                "fail"
+            }
+        };
+        checks.insert(name.to_string(), json!(status));
+    };
+
+    // The previous version is still served after a failed reload, but the data files
+    // on disk are broken and need fixing
+    record("content", state.content.reload_error().map_or(Ok(()), Err));
+
+    let data_dir = state.config.data_dir.clone();
+    let readable = tokio::task::spawn_blocking(move || {
+        std::fs::read_dir(&data_dir).map(|_| ()).map_err(|err| format!("{}: {}", data_dir.display(), err))
+    })
+    .await
+    .map_err(|err| err.to_string())
+    .and_then(|result| result);
+    record("data_dir", readable);
+
+    record("database", state.messages.ping().await.map_err(|err| err.to_string()));
+
+    for (name, result) in state.notifiers.check().await {
+        record(&format!("notifier_{}", name), result.map_err(|err| err.to_string()));
+    }
+
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    let body: Value = json!({
+        "status": if ready { "ok" } else { "unavailable" },
+        "checks": checks,
+    });
+    (status, Json(body))
+}
+
+/// `healthcheck` subcommand: ask the server on this machine for /healthz.
+/// The runtime image has no shell or curl, so Docker's HEALTHCHECK runs the binary itself.
+pub async fn probe(config: &Config) -> bool {
+    // A wildcard bind address is reachable through loopback
+    let host = match config.host {
+        IpAddr::V4(ip) if ip.is_unspecified() => "127.0.0.1".to_string(),
+        IpAddr::V6(ip) if ip.is_unspecified() => "[::1]".to_string(),
+        IpAddr::V4(ip) => ip.to_string(),
+        IpAddr::V6(ip) => format!("[{}]", ip),
+    };
+    let url = format!("http://{}:{}/healthz", host, config.port);
+
+    let client = match reqwest::Client::builder().timeout(Duration::from_secs(3)).build() {
+        Ok(client) => client,
+        Err(err) => {
+            eprintln!("Health check failed: {}", err);
+            return false;
+        }
+    };
+    match client.get(&url).send().await {
+        Ok(response) if response.status().is_success() => true,
+        Ok(response) => {
+            eprintln!("Health check failed: {} returned {}", url, response.status());
+            false
+        }
+        Err(err) => {
+            eprintln!("Health check failed: {}", err);
+            false
+        }
+    }
+}
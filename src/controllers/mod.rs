@@ -2,4 +2,7 @@ pub mod home;
 pub mod about;
 pub mod experience;
 pub mod projects;
-pub mod contact;
\ No newline at end of file
+pub mod blog;
+pub mod search;
+pub mod contact;
+pub mod admin;pub mod health;
@@ -1,3 +1,68 @@
 use crate::generate_page_handler;
+use crate::i18n::Lang;
+use crate::state::AppState;
+use crate::views::projects_view;
+use axum::{
+    extract::{Path, Query, State},
+    response::{IntoResponse, Redirect, Response},
+};
+use axum_htmx::HxTarget;
+use serde::{Deserialize, Serialize};
 
-generate_page_handler!(handler, "projects", projects_view);
+// `?tag=Rust&q=dashboard`; both optional, blank values are ignored
+#[derive(Deserialize, Serialize, Default)]
+#[serde(default)]
+pub struct ProjectFilter {
+    pub tag: Option<String>,
+    pub q: Option<String>,
+}
+
+impl ProjectFilter {
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref().map(str::trim).filter(|tag| !tag.is_empty())
+    }
+
+    pub fn query(&self) -> Option<&str> {
+        self.q.as_deref().map(str::trim).filter(|q| !q.is_empty())
+    }
+}
+
+// The filter bar and the tags swap just the results; anything else gets the whole section.
+// Search text is sent as it is typed, so those results are not kept in the render cache,
+// and neither are tags no project has
+generate_page_handler!(handler, "projects", [filter: Query<ProjectFilter>, target: HxTarget], |site, state, csrf, locale| {
+    let page = if target.0.as_deref() == Some(projects_view::RESULTS_ID) {
+        projects_view::render_results(&site, &filter, locale)
+    } else {
+        projects_view::render(&site, &filter, locale)
+    };
+    let tags = site.portfolio.tags();
+    let known_tag = filter.tag().is_none_or(|tag| tags.iter().any(|(known, _)| known.eq_ignore_ascii_case(tag)));
+    Some(if filter.query().is_some() || !known_tag { page.uncached() } else { page })
+});
+
+generate_page_handler!(detail, "projects", [slug: Path<String>], |site, state, csrf, locale| {
+    site.portfolio.project(&slug).map(|project| projects_view::render_detail(project, locale))
+});
+
+/// `GET /projects/demo/{slug}`: redirect to the live demo
+pub async fn demo(State(state): State<AppState>, lang: Lang, Path(slug): Path<String>) -> Response {
+    let site = state.content.current(lang.locale);
+    let url = site.portfolio.project(&slug).and_then(|project| project.demo_url.clone());
+    redirect_or_not_found(state, lang, url).await
+}
+
+/// `GET /projects/code/{slug}`: redirect to the repository
+pub async fn code(State(state): State<AppState>, lang: Lang, Path(slug): Path<String>) -> Response {
+    let site = state.content.current(lang.locale);
+    let url = site.portfolio.project(&slug).and_then(|project| project.repository_url.clone());
+    redirect_or_not_found(state, lang, url).await
+}
+
+// Unknown projects and projects without that link get the 404 page
+async fn redirect_or_not_found(state: AppState, lang: Lang, url: Option<String>) -> Response {
+    match url {
+        Some(url) => Redirect::to(&url).into_response(),
+        None => crate::not_found(State(state), lang).await.into_response(),
+    }
+}
@@ -0,0 +1,48 @@
+use crate::csrf::CsrfToken;
+use crate::generate_page_handler;
+use crate::i18n::Lang;
+use crate::render_cache::Key;
+use crate::state::AppState;
+use crate::views::search_view;
+use axum::{
+    extract::{Query, State},
+    response::{Html, IntoResponse, Response},
+};
+use axum_htmx::{HxRequest, HxTarget};
+use serde::{Deserialize, Serialize};
+
+// `?q=rust htmx`
+#[derive(Deserialize, Serialize, Default)]
+#[serde(default)]
+pub struct SearchQuery {
+    q: Option<String>,
+}
+
+impl SearchQuery {
+    pub fn text(&self) -> &str {
+        self.q.as_deref().unwrap_or_default().trim()
+    }
+}
+
+// Queries rarely repeat; cached, they would only push pages out of the render cache
+generate_page_handler!(results, "search", [query: Query<SearchQuery>], |site, state, csrf, locale| {
+    Some(search_view::render(&site.search.search(query.text()), query.text(), locale).uncached())
+});
+
+/// `GET /search?q=`: the results page, or just the dropdown of the header search box
+pub async fn search(
+    State(state): State<AppState>,
+    HxRequest(is_htmx): HxRequest,
+    HxTarget(target): HxTarget,
+    key: Key,
+    csrf: CsrfToken,
+    lang: Lang,
+    query: Query<SearchQuery>,
+) -> Response {
+    if target.as_deref() == Some(search_view::DROPDOWN_ID) {
+        let site = state.content.current(lang.locale);
+        let hits = site.search.search(query.text());
+        return Html(search_view::render_dropdown(&hits, query.text(), lang.locale).into_string()).into_response();
+    }
+    results(State(state), HxRequest(is_htmx), key, csrf, lang, query).await
+}
@@ -0,0 +1,296 @@
+use crate::auth::constant_time_eq;
+use crate::config::Config;
+use crate::i18n::{Lang, Locale};
+use crate::state::AppState;
+use crate::views;
+use axum::{
+    body::{Body, to_bytes},
+    extract::{FromRequestParts, Request, State},
+    http::{
+        HeaderMap, Method, StatusCode,
+        header::{CONTENT_TYPE, COOKIE, SET_COOKIE},
+        request::Parts,
+    },
+    middleware::Next,
+    response::{Html, IntoResponse, Response},
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use hmac::{Hmac, Mac};
+use maud::{Markup, html};
+use rand::RngCore;
+use sha2::Sha256;
+
+// Cross-site request forgery protection with a signed double-submit cookie:
+// the token in the cookie must come back in a header or form field on every
+// state-changing request, which a third-party page can't read or forge
+
+pub const COOKIE_NAME: &str = "csrf_token";
+/// Sent by htmx, configured through `hx-headers` on the page body
+pub const HEADER_NAME: &str = "x-csrf-token";
+/// Hidden field for plain form posts
+pub const FORM_FIELD: &str = "csrf_token";
+
+/// Larger form bodies are rejected before looking for the token
+const MAX_FORM_BYTES: usize = 64 * 1024;
+/// Rendered instead of the token into pages shared between visitors (see `render_cache`);
+/// not a valid token, so a page served without filling it in can't post anything
+const PLACEHOLDER: &str = "csrf-token-placeholder";
+
+/// Token of the current session, added to the request by [`protect`]
+#[derive(Clone, Debug)]
+pub struct CsrfToken(String);
+
+impl CsrfToken {
+    /// Hidden input to place inside every `<form method="post">`
+    pub fn hidden_field(&self) -> Markup {
+        html! {
+            input type="hidden" name=(FORM_FIELD) value=(self.0);
+        }
+    }
+
+    /// Value for `hx-headers`, so every htmx request carries the token
+    pub fn hx_headers(&self) -> String {
+        serde_json::json!({ HEADER_NAME: self.0 }).to_string()
+    }
+
+    /// Stands in for the visitor's token in a page rendered once for everyone
+    pub fn placeholder() -> Self {
+        CsrfToken(PLACEHOLDER.to_string())
+    }
+
+    /// `html` rendered with the placeholder, carrying this token instead
+    pub fn fill(&self, html: &str) -> String {
+        html.replace(PLACEHOLDER, &self.0)
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for CsrfToken {
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts.extensions.get::<CsrfToken>().cloned().ok_or((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "CSRF middleware is not installed on this route",
+        ))
+    }
+}
+
+/// Issues tokens of the form `nonce.signature`, so only values created by
+/// this server are accepted as a session token
+pub struct Csrf {
+    key: [u8; 32],
+}
+
+impl Csrf {
+    pub fn new(key: [u8; 32]) -> Self {
+        Csrf { key }
+    }
+
+    fn issue(&self) -> String {
+        let mut nonce = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let nonce = URL_SAFE_NO_PAD.encode(nonce);
+        let signature = URL_SAFE_NO_PAD.encode(self.mac(&nonce).finalize().into_bytes());
+        format!("{}.{}", nonce, signature)
+    }
+
+    fn is_valid(&self, token: &str) -> bool {
+        let Some((nonce, signature)) = token.split_once('.') else {
+            return false;
+        };
+        let Ok(signature) = URL_SAFE_NO_PAD.decode(signature) else {
+            return false;
+        };
+        self.mac(nonce).verify_slice(&signature).is_ok()
+    }
+
+    /// Whether an unsafe request may go through: the cookie holds a token issued here
+    /// and the header or form field repeats it
+    fn accepts(&self, cookie: Option<&str>, submitted: Option<&str>) -> bool {
+        match (cookie, submitted) {
+            (Some(cookie), Some(submitted)) => {
+                self.is_valid(cookie) && constant_time_eq(submitted.as_bytes(), cookie.as_bytes())
+            }
+            _ => false,
+        }
+    }
+
+    fn mac(&self, nonce: &str) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts any key length");
+        mac.update(b"csrf:");
+        mac.update(nonce.as_bytes());
+        mac
+    }
+}
+
+/// Middleware: hands out the session token and rejects unsafe requests without it
+pub async fn protect(State(state): State<AppState>, mut request: Request, next: Next) -> Response {
+    let csrf = &state.csrf;
+    let existing = cookie_value(request.headers(), COOKIE_NAME)
+        .filter(|token| csrf.is_valid(token))
+        .map(str::to_string);
+    let is_new = existing.is_none();
+    let token = existing.unwrap_or_else(|| csrf.issue());
+
+    if !is_safe(request.method()) {
+        let (submitted, rebuilt) = submitted_token(request).await;
+        // A freshly issued token means the browser sent none, so nothing can match
+        let cookie = if is_new { None } else { Some(token.as_str()) };
+        if !csrf.accepts(cookie, submitted.as_deref()) {
+            tracing::warn!("Rejected request: missing or invalid CSRF token");
+            return with_cookie(forbidden(&state.config, &rebuilt), &token, is_new);
+        }
+        request = rebuilt;
+    }
+
+    request.extensions_mut().insert(CsrfToken(token.clone()));
+    with_cookie(next.run(request).await, &token, is_new)
+}
+
+fn is_safe(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE)
+}
+
+/// Token from the header, or from the form body, which is buffered and put back
+async fn submitted_token(request: Request) -> (Option<String>, Request) {
+    if let Some(token) = request.headers().get(HEADER_NAME).and_then(|v| v.to_str().ok()) {
+        return (Some(token.to_string()), request);
+    }
+
+    let is_form = request
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/x-www-form-urlencoded"));
+    if !is_form {
+        return (None, request);
+    }
+
+    let (parts, body) = request.into_parts();
+    let Ok(bytes) = to_bytes(body, MAX_FORM_BYTES).await else {
+        return (None, Request::from_parts(parts, Body::empty()));
+    };
+    let token = form_urlencoded::parse(&bytes)
+        .find(|(name, _)| name == FORM_FIELD)
+        .map(|(_, value)| value.into_owned());
+    (token, Request::from_parts(parts, Body::from(bytes)))
+}
+
+pub(crate) fn cookie_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+fn with_cookie(mut response: Response, token: &str, is_new: bool) -> Response {
+    if is_new {
+        let cookie = format!("{}={}; Path=/; HttpOnly; SameSite=Lax", COOKIE_NAME, token);
+        if let Ok(value) = cookie.parse() {
+            response.headers_mut().append(SET_COOKIE, value);
+        }
+    }
+    response
+}
+
+fn forbidden(config: &Config, request: &Request) -> Response {
+    let locale = request.extensions().get::<Lang>().map_or(Locale::DEFAULT, |lang| lang.locale);
+    if request.headers().contains_key("hx-request") {
+        views::error_view::htmx_error(StatusCode::FORBIDDEN, views::error_view::forbidden_content(locale))
+    } else {
+        (StatusCode::FORBIDDEN, Html(views::error_view::forbidden(config, locale).into_string())).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn csrf() -> Csrf {
+        Csrf::new([3; 32])
+    }
+
+    #[test]
+    fn accepts_token_repeated_from_cookie() {
+        let csrf = csrf();
+        let token = csrf.issue();
+        assert!(csrf.is_valid(&token));
+        assert!(csrf.accepts(Some(&token), Some(&token)));
+    }
+
+    #[test]
+    fn rejects_forged_token() {
+        let csrf = csrf();
+        // Signed with another key, or not signed at all
+        let foreign = Csrf::new([4; 32]).issue();
+        assert!(!csrf.accepts(Some(&foreign), Some(&foreign)));
+        let token = csrf.issue();
+        let (nonce, _) = token.split_once('.').unwrap();
+        let unsigned = format!("{}.{}", nonce, URL_SAFE_NO_PAD.encode([0u8; 32]));
+        assert!(!csrf.accepts(Some(&unsigned), Some(&unsigned)));
+        for malformed in ["", "token", "nonce.not base64!", PLACEHOLDER] {
+            assert!(!csrf.accepts(Some(malformed), Some(malformed)), "{:?}", malformed);
+        }
+        // Valid, but not the visitor's own
+        assert!(!csrf.accepts(Some(&token), Some(&csrf.issue())));
+    }
+
+    #[test]
+    fn rejects_missing_cookie_or_token() {
+        let csrf = csrf();
+        let token = csrf.issue();
+        assert!(!csrf.accepts(None, Some(&token)));
+        assert!(!csrf.accepts(Some(&token), None));
+        assert!(!csrf.accepts(None, None));
+    }
+
+    #[test]
+    fn reads_cookie_among_others() {
+        let mut headers = HeaderMap::new();
+        headers.append(COOKIE, "theme=dark; csrf_token=abc.def".parse().unwrap());
+        headers.append(COOKIE, "admin_session=xyz".parse().unwrap());
+        assert_eq!(cookie_value(&headers, COOKIE_NAME), Some("abc.def"));
+        assert_eq!(cookie_value(&headers, "admin_session"), Some("xyz"));
+        assert_eq!(cookie_value(&headers, "missing"), None);
+    }
+
+    #[tokio::test]
+    async fn reads_submitted_token_from_header_or_form() {
+        let request = Request::post("/contact").header(HEADER_NAME, "abc.def").body(Body::empty()).unwrap();
+        assert_eq!(submitted_token(request).await.0.as_deref(), Some("abc.def"));
+
+        let body = "name=Ada&csrf_token=abc.def";
+        let request = Request::post("/contact")
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(Body::from(body))
+            .unwrap();
+        let (token, request) = submitted_token(request).await;
+        assert_eq!(token.as_deref(), Some("abc.def"));
+        // The handler still gets the whole form
+        let rebuilt = to_bytes(request.into_body(), MAX_FORM_BYTES).await.unwrap();
+        assert_eq!(rebuilt, body.as_bytes());
+
+        let request = Request::post("/contact")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"csrf_token":"abc.def"}"#))
+            .unwrap();
+        assert_eq!(submitted_token(request).await.0, None);
+    }
+
+    #[test]
+    fn fill_replaces_placeholder() {
+        let page = |csrf: &CsrfToken| {
+            format!("<body hx-headers='{}'>{}</body>", csrf.hx_headers(), csrf.hidden_field().into_string())
+        };
+        let token = CsrfToken("abc.def".to_string());
+
+        let filled = token.fill(&page(&CsrfToken::placeholder()));
+        assert!(!filled.contains(PLACEHOLDER));
+        assert_eq!(filled, page(&token));
+        assert_eq!(token.fill("<p>no form</p>"), "<p>no form</p>");
+    }
+}
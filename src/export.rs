@@ -0,0 +1,264 @@
+use crate::assets;
+use crate::config::Config;
+use crate::content::ContentStore;
+use crate::feed;
+use crate::files;
+use crate::i18n::Locale;
+use crate::routes::Route;
+use crate::views::{blog_view, error_view};
+use axum::{
+    body::{Body, Bytes, to_bytes},
+    extract::Request,
+    http::StatusCode,
+    response::Response,
+};
+use std::{
+    collections::BTreeSet,
+    convert::Infallible,
+    fmt, fs,
+    future::poll_fn,
+    io,
+    path::{Component, Path, PathBuf},
+};
+use tower::Service;
+
+// `basic-web export --out dist/`: the site prerendered to files, for hosting on any
+// static file server. Every page is requested from the router in-process, unprefixed
+// and under each language prefix, and saved twice: `about/index.html` with the
+// layout and `about/_partial.html` with the fragment htmx swaps into <main>. Feeds,
+// sitemaps, robots.txt, Open Graph images and the static files (plain and
+// fingerprinted names) are written next to them. Pages made per visitor or per query
+// (contact form, search, `?page=N`, admin) are left to the running server; see the
+// README for a file server configuration that forwards them.
+
+/// Saved next to each page's `index.html`, served when the request has `HX-Request`
+pub const PARTIAL_FILE: &str = "_partial.html";
+
+/// Limit for buffering a response; the largest are the Open Graph images
+const MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+#[derive(Debug)]
+pub enum ExportError {
+    /// Path that can't be requested or written as a file
+    InvalidPath(String),
+    /// Path and the unexpected status it was answered with
+    Request(String, StatusCode),
+    Read(PathBuf, io::Error),
+    Write(PathBuf, io::Error),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::InvalidPath(path) => write!(f, "can't export {}", path),
+            ExportError::Request(path, status) => write!(f, "{} answered {}", path, status),
+            ExportError::Read(path, err) => write!(f, "failed to read {}: {}", path.display(), err),
+            ExportError::Write(path, err) => write!(f, "failed to write {}: {}", path.display(), err),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+/// Render the site into `out`; returns the number of files written
+pub async fn run<S>(mut app: S, config: &Config, content: &ContentStore, out: &Path) -> Result<usize, ExportError>
+where
+    S: Service<Request, Response = Response, Error = Infallible>,
+{
+    let mut export = Export { out, written: 0 };
+    let mut images = BTreeSet::new();
+
+    for path in pages(content) {
+        for url in localized(&path) {
+            let page = required(&mut app, &url).await?;
+            let page = without_session_token(&String::from_utf8_lossy(&page));
+            images.extend(og_images(config, &page));
+            export.write(&url, Some("index.html"), page.as_bytes())?;
+
+            let Some(partial) = fetch(&mut app, &url, true).await? else {
+                return Err(ExportError::Request(url, StatusCode::NOT_FOUND));
+            };
+            export.write(&url, Some(PARTIAL_FILE), &partial)?;
+        }
+    }
+
+    for path in [feed::RSS_PATH, feed::ATOM_PATH] {
+        for url in localized(path) {
+            let document = required(&mut app, &url).await?;
+            export.write(&url, None, &document)?;
+        }
+    }
+
+    for path in ["/sitemap.xml", "/robots.txt"] {
+        let document = required(&mut app, path).await?;
+        export.write(path, None, &document)?;
+    }
+    // Parts of the sitemap when /sitemap.xml is an index; a single sitemap has none
+    for number in 1.. {
+        let path = format!("/sitemaps/{}.xml", number);
+        let Some(document) = fetch(&mut app, &path, false).await? else {
+            break;
+        };
+        export.write(&path, None, &document)?;
+    }
+
+    for path in images {
+        let image = required(&mut app, &path).await?;
+        export.write(&path, None, &image)?;
+    }
+
+    // Sent by the file server for missing pages, in the language of the prefix
+    for locale in Locale::ALL {
+        let page = error_view::not_found(config, locale).into_string();
+        let dir = if locale == Locale::DEFAULT { "/".to_string() } else { locale.path("/") };
+        export.write(&dir, Some("404.html"), page.as_bytes())?;
+    }
+
+    // Under both names, like the server: pages link to the fingerprinted one
+    let static_files = files::walk(&config.static_dir).map_err(|err| ExportError::Read(config.static_dir.clone(), err))?;
+    for file in static_files {
+        if file.starts_with(&config.data_dir) {
+            continue;
+        }
+        let Some(relative) = file.strip_prefix(&config.static_dir).ok().and_then(Path::to_str) else {
+            continue;
+        };
+        let relative = relative.replace('\\', "/");
+        let bytes = files::read(&file).map_err(|err| ExportError::Read(file.clone(), err))?;
+        let plain = format!("/static/{}", relative);
+        let fingerprinted = assets::asset_url(&relative);
+        export.write(&plain, None, &bytes)?;
+        if fingerprinted != plain {
+            export.write(&fingerprinted, None, &bytes)?;
+        }
+    }
+
+    Ok(export.written)
+}
+
+struct Export<'a> {
+    out: &'a Path,
+    written: usize,
+}
+
+impl Export<'_> {
+    /// Write the document for the URL path `url`, or `file` in its directory
+    fn write(&mut self, url: &str, file: Option<&str>, contents: &[u8]) -> Result<(), ExportError> {
+        let relative = Path::new(url.trim_start_matches('/'));
+        // Only plain names, so a path can't point outside the output directory
+        if !relative.components().all(|part| matches!(part, Component::Normal(_))) {
+            return Err(ExportError::InvalidPath(url.to_string()));
+        }
+        let target = match file {
+            Some(file) => self.out.join(relative).join(file),
+            None => self.out.join(relative),
+        };
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|err| ExportError::Write(parent.to_path_buf(), err))?;
+        }
+        fs::write(&target, contents).map_err(|err| ExportError::Write(target.clone(), err))?;
+        self.written += 1;
+        Ok(())
+    }
+}
+
+// Every public page that is the same for all visitors, without the language prefix
+fn pages(content: &ContentStore) -> Vec<String> {
+    let content = content.current(Locale::DEFAULT);
+    let mut pages = Vec::new();
+    for route in Route::ALL {
+        match route {
+            Route::Home | Route::About | Route::Experience | Route::Projects | Route::Blog => {
+                pages.push(route.path().into_owned())
+            }
+            // The form carries a single-use token that expires
+            Route::Contact => continue,
+            // Listed below, one per project
+            Route::ProjectDetail(_) => continue,
+            // Redirects to other sites, which a static host can't answer with
+            Route::ProjectDemo(_) | Route::ProjectCode(_) => continue,
+            // Depend on the query or the signed-in admin
+            Route::Search | Route::AdminMessages => continue,
+        }
+    }
+
+    for project in &content.portfolio.projects {
+        pages.push(Route::ProjectDetail(project.slug.clone()).path().into_owned());
+    }
+    let posts: Vec<_> = content.blog.posts().into_iter().filter(|post| !post.draft).collect();
+    for post in &posts {
+        pages.push(blog_view::post_path(&post.slug));
+    }
+    let mut tags: Vec<&str> = posts.iter().flat_map(|post| &post.tags).map(String::as_str).collect();
+    tags.sort_unstable();
+    tags.dedup();
+    pages.extend(tags.into_iter().map(blog_view::tag_path));
+    pages
+}
+
+// Unprefixed (in the default language, as nothing is negotiated) and in each language
+fn localized(path: &str) -> Vec<String> {
+    std::iter::once(path.to_string())
+        .chain(Locale::ALL.into_iter().map(|locale| locale.path(path)))
+        .collect()
+}
+
+// Body of a 200 response; None for a 404
+async fn fetch<S>(app: &mut S, path: &str, htmx: bool) -> Result<Option<Bytes>, ExportError>
+where
+    S: Service<Request, Response = Response, Error = Infallible>,
+{
+    let mut request = Request::get(path);
+    if htmx {
+        request = request.header("hx-request", "true");
+    }
+    let request = request.body(Body::empty()).map_err(|_| ExportError::InvalidPath(path.to_string()))?;
+
+    let Ok(()) = poll_fn(|cx| app.poll_ready(cx)).await;
+    let Ok(response) = app.call(request).await;
+    match response.status() {
+        StatusCode::OK => {}
+        StatusCode::NOT_FOUND => return Ok(None),
+        status => return Err(ExportError::Request(path.to_string(), status)),
+    }
+    to_bytes(response.into_body(), MAX_BODY_BYTES)
+        .await
+        .map(Some)
+        .map_err(|_| ExportError::Request(path.to_string(), StatusCode::INTERNAL_SERVER_ERROR))
+}
+
+async fn required<S>(app: &mut S, path: &str) -> Result<Bytes, ExportError>
+where
+    S: Service<Request, Response = Response, Error = Infallible>,
+{
+    fetch(app, path, false)
+        .await?
+        .ok_or_else(|| ExportError::Request(path.to_string(), StatusCode::NOT_FOUND))
+}
+
+// The page body sends the visitor's CSRF token with every htmx request; an exported
+// page is shared by everyone, so it goes without, and the contact form served by the
+// backend falls back to its own hidden field
+fn without_session_token(html: &str) -> String {
+    const ATTRIBUTE: &str = " hx-headers=\"";
+    let Some(start) = html.find(ATTRIBUTE) else {
+        return html.to_string();
+    };
+    let value = start + ATTRIBUTE.len();
+    match html[value..].find('"') {
+        Some(end) => format!("{}{}", &html[..start], &html[value + end + 1..]),
+        None => html.to_string(),
+    }
+}
+
+// Paths of the `og:image` cards a page links to, which are rendered on request
+fn og_images(config: &Config, html: &str) -> Vec<String> {
+    const META: &str = "<meta property=\"og:image\" content=\"";
+    html.match_indices(META)
+        .filter_map(|(start, _)| {
+            let value = &html[start + META.len()..];
+            let url = &value[..value.find('"')?];
+            Some(url.strip_prefix(config.base_url.as_str())?.to_string())
+        })
+        .collect()
+}
@@ -0,0 +1,195 @@
+use crate::config::Config;
+use crate::content::{Content, FeedKind};
+use crate::i18n::Locale;
+use crate::views::blog_view;
+use chrono::{DateTime, NaiveDate, NaiveTime, SecondsFormat, Utc};
+use std::fmt::Write;
+
+// RSS 2.0 (/feed.xml) and Atom (/atom.xml) feeds with the entries of `feed.json` and
+// the blog posts, newest first. Like every page they exist in each language (/en/feed.xml).
+
+pub const RSS_PATH: &str = "/feed.xml";
+pub const ATOM_PATH: &str = "/atom.xml";
+
+/// Older entries are dropped from the feeds, not from the site
+const MAX_ENTRIES: usize = 20;
+
+struct Item<'a> {
+    /// Absolute URL, plus a fragment for feed entries that share a page
+    id: String,
+    url: String,
+    title: &'a str,
+    summary: &'a str,
+    category: &'static str,
+    published: DateTime<Utc>,
+    updated: DateTime<Utc>,
+}
+
+fn items<'a>(config: &Config, content: &'a Content, locale: Locale) -> Vec<Item<'a>> {
+    let entries = content.feed.entries.iter().map(|entry| {
+        let url = config.url(&locale.path(&entry.path()));
+        Item {
+            id: format!("{}#{}", url, entry.id),
+            url,
+            title: &entry.title,
+            summary: &entry.summary,
+            category: match entry.kind {
+                FeedKind::Project => locale.t("feed.project"),
+                FeedKind::Experience => locale.t("feed.experience"),
+                FeedKind::Announcement => locale.t("feed.announcement"),
+            },
+            published: midnight(entry.date),
+            updated: midnight(entry.updated.unwrap_or(entry.date)),
+        }
+    });
+    let posts = content.blog.posts().into_iter().filter(|post| !post.draft).map(|post| {
+        let url = config.url(&locale.path(&blog_view::post_path(&post.slug)));
+        Item {
+            id: url.clone(),
+            url,
+            title: &post.title,
+            summary: &post.summary,
+            category: locale.t("feed.post"),
+            published: midnight(post.date),
+            updated: midnight(post.date),
+        }
+    });
+
+    let mut items: Vec<Item> = entries.chain(posts).collect();
+    items.sort_by(|a, b| b.published.cmp(&a.published).then_with(|| a.id.cmp(&b.id)));
+    items.truncate(MAX_ENTRIES);
+    items
+}
+
+// Dates in the data files have no time of day
+fn midnight(date: NaiveDate) -> DateTime<Utc> {
+    date.and_time(NaiveTime::MIN).and_utc()
+}
+
+// Time of the most recent change; an empty feed is as new as the request
+fn last_updated(items: &[Item]) -> DateTime<Utc> {
+    items.iter().map(|item| item.updated).max().unwrap_or_else(Utc::now)
+}
+
+pub fn rss(config: &Config, content: &Content, locale: Locale) -> String {
+    let items = items(config, content, locale);
+    let title = locale.t_with("feed.title", &[("name", &config.site_name)]);
+
+    let mut xml = String::new();
+    let _ = write!(
+        xml,
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom">
+<channel>
+<title>{}</title>
+<link>{}</link>
+<description>{}</description>
+<language>{}</language>
+<lastBuildDate>{}</lastBuildDate>
+<atom:link href="{}" rel="self" type="application/rss+xml"/>
+"#,
+        escape(&title),
+        escape(&config.url(&locale.path("/"))),
+        escape(locale.t("feed.description")),
+        locale.code(),
+        last_updated(&items).to_rfc2822(),
+        escape(&config.url(&locale.path(RSS_PATH))),
+    );
+    for item in &items {
+        let _ = write!(
+            xml,
+            r#"<item>
+<title>{}</title>
+<link>{}</link>
+<guid isPermaLink="{}">{}</guid>
+<description>{}</description>
+<category>{}</category>
+<pubDate>{}</pubDate>
+</item>
+"#,
+            escape(item.title),
+            escape(&item.url),
+            item.id == item.url,
+            escape(&item.id),
+            escape(item.summary),
+            escape(item.category),
+            item.published.to_rfc2822(),
+        );
+    }
+    xml.push_str("</channel>\n</rss>\n");
+    xml
+}
+
+pub fn atom(config: &Config, content: &Content, locale: Locale) -> String {
+    let items = items(config, content, locale);
+    let title = locale.t_with("feed.title", &[("name", &config.site_name)]);
+    let self_url = config.url(&locale.path(ATOM_PATH));
+
+    let mut xml = String::new();
+    let _ = write!(
+        xml,
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom" xml:lang="{}">
+<title>{}</title>
+<subtitle>{}</subtitle>
+<id>{}</id>
+<link href="{}" rel="self" type="application/atom+xml"/>
+<link href="{}" rel="alternate" type="text/html"/>
+<updated>{}</updated>
+<author><name>{}</name></author>
+"#,
+        locale.code(),
+        escape(&title),
+        escape(locale.t("feed.description")),
+        escape(&self_url),
+        escape(&self_url),
+        escape(&config.url(&locale.path("/"))),
+        timestamp(last_updated(&items)),
+        escape(&config.site_name),
+    );
+    for item in &items {
+        let _ = write!(
+            xml,
+            r#"<entry>
+<title>{}</title>
+<id>{}</id>
+<link href="{}" rel="alternate" type="text/html"/>
+<published>{}</published>
+<updated>{}</updated>
+<summary>{}</summary>
+<category term="{}"/>
+</entry>
+"#,
+            escape(item.title),
+            escape(&item.id),
+            escape(&item.url),
+            timestamp(item.published),
+            timestamp(item.updated),
+            escape(item.summary),
+            escape(item.category),
+        );
+    }
+    xml.push_str("</feed>\n");
+    xml
+}
+
+// RFC 3339, as Atom requires
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Text and attribute values in XML documents
+pub fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
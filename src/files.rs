@@ -0,0 +1,394 @@
+use crate::conditional;
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{
+        HeaderMap, HeaderValue, Method, StatusCode,
+        header::{
+            ACCEPT_ENCODING, ACCEPT_RANGES, ALLOW, CONTENT_ENCODING, CONTENT_RANGE, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE,
+            IF_NONE_MATCH, IF_RANGE, LAST_MODIFIED, RANGE, VARY,
+        },
+    },
+    response::{IntoResponse, Response},
+};
+use std::{
+    borrow::Cow,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::SystemTime,
+};
+
+// Files of the static directory: pages, content and JSON-LD. They are read from disk,
+// except when the binary is built with the `embed` feature and the static directory
+// is not there: then the copy of `static/` compiled into the executable is used, so a
+// single file can be deployed. With files on disk (development) nothing changes.
+
+#[cfg(feature = "embed")]
+#[derive(rust_embed::RustEmbed)]
+#[folder = "static/"]
+struct Embedded;
+
+/// Precompressed files next to the original, most compact first
+const PRECOMPRESSED: [(&str, &str); 3] = [("br", ".br"), ("zstd", ".zst"), ("gzip", ".gz")];
+
+/// Where the embedded files stand in for the static directory; unset when reading from disk
+static EMBEDDED_ROOT: OnceLock<PathBuf> = OnceLock::new();
+
+struct EmbeddedFile {
+    data: Cow<'static, [u8]>,
+    hash: [u8; 32],
+    modified: Option<SystemTime>,
+    mime: String,
+}
+
+/// Serve `static_dir` from the binary when it is not on disk and the files were
+/// embedded; returns whether they are used
+pub fn init(static_dir: &Path) -> bool {
+    if !cfg!(feature = "embed") || static_dir.is_dir() {
+        return false;
+    }
+    // Set once at startup; a second call keeps the first directory
+    let _ = EMBEDDED_ROOT.set(static_dir.to_path_buf());
+    true
+}
+
+pub fn is_embedded() -> bool {
+    EMBEDDED_ROOT.get().is_some()
+}
+
+pub fn read(path: &Path) -> io::Result<Vec<u8>> {
+    match embedded_key(path) {
+        Some(key) => embedded_file(&key).map(|file| file.data.into_owned()).ok_or_else(not_found),
+        None => fs::read(path),
+    }
+}
+
+pub fn read_to_string(path: &Path) -> io::Result<String> {
+    String::from_utf8(read(path)?).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+pub fn is_file(path: &Path) -> bool {
+    match embedded_key(path) {
+        Some(key) => embedded_file(&key).is_some(),
+        None => path.is_file(),
+    }
+}
+
+pub fn modified(path: &Path) -> Option<SystemTime> {
+    match embedded_key(path) {
+        Some(key) => embedded_file(&key)?.modified,
+        None => fs::metadata(path).ok()?.modified().ok(),
+    }
+}
+
+/// Names of the files directly in `dir`
+pub fn file_names(dir: &Path) -> io::Result<Vec<String>> {
+    match embedded_key(dir) {
+        Some(key) => {
+            let prefix = if key.is_empty() { key } else { format!("{}/", key) };
+            let names: Vec<String> = embedded_keys()
+                .filter_map(|name| Some(name.strip_prefix(prefix.as_str())?.to_string()))
+                .filter(|name| !name.contains('/'))
+                .collect();
+            if names.is_empty() { Err(not_found()) } else { Ok(names) }
+        }
+        None => fs::read_dir(dir)?
+            .map(|entry| {
+                let entry = entry?;
+                Ok((entry.file_type()?.is_file(), entry.file_name().to_string_lossy().into_owned()))
+            })
+            .filter_map(|entry| match entry {
+                Ok((true, name)) => Some(Ok(name)),
+                Ok((false, _)) => None,
+                Err(err) => Some(Err(err)),
+            })
+            .collect(),
+    }
+}
+
+/// Every file under `dir`, subdirectories included
+pub fn walk(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    if let Some(key) = embedded_key(dir) {
+        let prefix = if key.is_empty() { key } else { format!("{}/", key) };
+        return Ok(embedded_keys()
+            .filter_map(|name| Some(dir.join(name.strip_prefix(prefix.as_str())?)))
+            .collect());
+    }
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(walk(&path)?);
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+/// Handler for the embedded static files, nested under /static: content type, weak
+/// ETag (the compression layer may still encode the body), Last-Modified, single
+/// byte ranges and precompressed `.br`/`.zst`/`.gz` siblings
+pub async fn serve(request: Request) -> Response {
+    if request.method() != Method::GET && request.method() != Method::HEAD {
+        return (StatusCode::METHOD_NOT_ALLOWED, [(ALLOW, "GET, HEAD")]).into_response();
+    }
+    let key = request.uri().path().trim_start_matches('/');
+    let Some(original) = embedded_file(key) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let headers = request.headers();
+    let mime = content_type(&original.mime);
+    let variants: Vec<(&str, EmbeddedFile)> = PRECOMPRESSED
+        .into_iter()
+        .filter_map(|(encoding, suffix)| Some((encoding, embedded_file(&format!("{}{}", key, suffix))?)))
+        .collect();
+    let has_variants = !variants.is_empty();
+    let (encoding, file) = preferred(headers, variants).map_or((None, original), |(encoding, file)| (Some(encoding), file));
+
+    let mut response = Response::new(Body::empty());
+    let response_headers = response.headers_mut();
+    if let Ok(mime) = HeaderValue::from_str(&mime) {
+        response_headers.insert(CONTENT_TYPE, mime);
+    }
+    if let Some(encoding) = encoding {
+        response_headers.insert(CONTENT_ENCODING, HeaderValue::from_static(encoding));
+    }
+    if has_variants {
+        response_headers.insert(VARY, HeaderValue::from_static("accept-encoding"));
+    }
+    let etag = conditional::etag(&file.hash, true);
+    response_headers.insert(ETAG, etag.clone());
+    if let Some(modified) = file.modified {
+        response_headers.insert(LAST_MODIFIED, conditional::http_date(modified));
+    }
+    response_headers.insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+
+    let not_modified = match headers.get(IF_NONE_MATCH) {
+        Some(header) => conditional::matches_any(header, &etag),
+        None => headers
+            .get(IF_MODIFIED_SINCE)
+            .is_some_and(|since| conditional::unmodified_since(response.headers(), since)),
+    };
+    if not_modified {
+        *response.status_mut() = StatusCode::NOT_MODIFIED;
+        return response;
+    }
+
+    let length = file.data.len();
+    match range_spec(headers).map(|range| byte_range(range, length)) {
+        Some(Some((start, end))) => {
+            *response.status_mut() = StatusCode::PARTIAL_CONTENT;
+            let content_range = format!("bytes {}-{}/{}", start, end, length);
+            if let Ok(value) = HeaderValue::from_str(&content_range) {
+                response.headers_mut().insert(CONTENT_RANGE, value);
+            }
+            *response.body_mut() = Body::from(file.data[start..=end].to_vec());
+        }
+        Some(None) => {
+            *response.status_mut() = StatusCode::RANGE_NOT_SATISFIABLE;
+            if let Ok(value) = HeaderValue::from_str(&format!("bytes */{}", length)) {
+                response.headers_mut().insert(CONTENT_RANGE, value);
+            }
+        }
+        None => *response.body_mut() = Body::from(file.data),
+    }
+    response
+}
+
+// Path relative to the static directory, when the embedded files are in use
+fn embedded_key(path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(EMBEDDED_ROOT.get()?).ok()?;
+    let parts: Option<Vec<&str>> = relative.components().map(|part| part.as_os_str().to_str()).collect();
+    Some(parts?.join("/"))
+}
+
+#[cfg(feature = "embed")]
+fn embedded_file(key: &str) -> Option<EmbeddedFile> {
+    let file = Embedded::get(key)?;
+    Some(EmbeddedFile {
+        hash: file.metadata.sha256_hash(),
+        modified: file
+            .metadata
+            .last_modified()
+            .map(|secs| std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs)),
+        mime: file.metadata.mimetype().to_string(),
+        data: file.data,
+    })
+}
+
+#[cfg(not(feature = "embed"))]
+fn embedded_file(_key: &str) -> Option<EmbeddedFile> {
+    None
+}
+
+#[cfg(feature = "embed")]
+fn embedded_keys() -> impl Iterator<Item = Cow<'static, str>> {
+    Embedded::iter()
+}
+
+#[cfg(not(feature = "embed"))]
+fn embedded_keys() -> impl Iterator<Item = Cow<'static, str>> {
+    std::iter::empty()
+}
+
+// Guessed from the extension of the original file, also for its compressed siblings
+fn content_type(mime: &str) -> String {
+    if mime.starts_with("text/") || mime.ends_with("javascript") || mime.ends_with("json") {
+        format!("{}; charset=utf-8", mime)
+    } else {
+        mime.to_string()
+    }
+}
+
+// The first precompressed variant the client accepts, in the order they are listed
+fn preferred<T>(headers: &HeaderMap, variants: Vec<(&'static str, T)>) -> Option<(&'static str, T)> {
+    variants.into_iter().find(|(encoding, _)| accepts(headers, encoding))
+}
+
+// `br`, `gzip;q=0.8` or `*` in Accept-Encoding, without `;q=0`
+fn accepts(headers: &HeaderMap, encoding: &str) -> bool {
+    headers
+        .get_all(ACCEPT_ENCODING)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|item| {
+            let mut parts = item.split(';').map(str::trim);
+            let name = parts.next().unwrap_or_default();
+            let refused = parts.any(|param| {
+                param
+                    .strip_prefix("q=")
+                    .and_then(|q| q.parse::<f32>().ok())
+                    .is_some_and(|q| q == 0.0)
+            });
+            (name.eq_ignore_ascii_case(encoding) || name == "*") && !refused
+        })
+}
+
+// The one range asked for in `Range: bytes=...`. If-Range needs a strong validator, so
+// a conditional range gets the whole file, and so do several ranges
+fn range_spec(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(RANGE)
+        .filter(|_| !headers.contains_key(IF_RANGE))
+        .and_then(|range| range.to_str().ok()?.strip_prefix("bytes="))
+        .filter(|spec| !spec.contains(','))
+}
+
+// `0-99`, `100-` or `-100` (the last 100 bytes) as inclusive offsets; None when
+// the range is malformed or outside the file
+fn byte_range(spec: &str, length: usize) -> Option<(usize, usize)> {
+    let last = length.checked_sub(1)?;
+    let (start, end) = spec.trim().split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => (length.saturating_sub(suffix.parse().ok()?), last),
+        (start, "") => (start.parse().ok()?, last),
+        (start, end) => (start.parse().ok()?, end.parse::<usize>().ok()?.min(last)),
+    };
+    (start <= end).then_some((start, end))
+}
+
+fn not_found() -> io::Error {
+    io::Error::from(io::ErrorKind::NotFound)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn open_and_suffix_ranges() {
+        assert_eq!(byte_range("0-", 100), Some((0, 99)));
+        assert_eq!(byte_range("10-19", 100), Some((10, 19)));
+        assert_eq!(byte_range("90-", 100), Some((90, 99)));
+        assert_eq!(byte_range("-10", 100), Some((90, 99)));
+        // Past the end: up to the last byte
+        assert_eq!(byte_range("50-500", 100), Some((50, 99)));
+        assert_eq!(byte_range("-500", 100), Some((0, 99)));
+    }
+
+    #[test]
+    fn unsatisfiable_ranges() {
+        // Answered 416 by `serve`
+        assert_eq!(byte_range("100-", 100), None);
+        assert_eq!(byte_range("100-199", 100), None);
+        assert_eq!(byte_range("-0", 100), None);
+        assert_eq!(byte_range("20-10", 100), None);
+        assert_eq!(byte_range("0-", 0), None);
+        assert_eq!(byte_range("a-b", 100), None);
+        assert_eq!(byte_range("10", 100), None);
+    }
+
+    #[test]
+    fn only_single_unconditional_ranges() {
+        assert_eq!(range_spec(&headers(&[("range", "bytes=0-99")])), Some("0-99"));
+        // The whole file instead
+        assert_eq!(range_spec(&headers(&[("range", "bytes=0-1,5-9")])), None);
+        assert_eq!(range_spec(&headers(&[("range", "bytes=0-99"), ("if-range", "\"abc\"")])), None);
+        assert_eq!(range_spec(&headers(&[("range", "items=0-99")])), None);
+        assert_eq!(range_spec(&headers(&[])), None);
+    }
+
+    #[test]
+    fn accepted_encodings() {
+        let headers = headers(&[("accept-encoding", "gzip;q=0.5, BR"), ("accept-encoding", "zstd;q=0")]);
+        assert!(accepts(&headers, "br"));
+        assert!(accepts(&headers, "gzip"));
+        assert!(!accepts(&headers, "zstd"));
+        assert!(!accepts(&HeaderMap::new(), "gzip"));
+
+        let any = self::headers(&[("accept-encoding", "*")]);
+        assert!(accepts(&any, "zstd"));
+        let identity_only = self::headers(&[("accept-encoding", "identity, *;q=0")]);
+        assert!(!accepts(&identity_only, "br"));
+    }
+
+    #[test]
+    fn picks_most_compact_accepted_variant() {
+        let variants = || vec![("br", 1), ("zstd", 2), ("gzip", 3)];
+        assert_eq!(preferred(&headers(&[("accept-encoding", "gzip, deflate, br")]), variants()), Some(("br", 1)));
+        assert_eq!(preferred(&headers(&[("accept-encoding", "gzip, zstd")]), variants()), Some(("zstd", 2)));
+        assert_eq!(preferred(&headers(&[("accept-encoding", "br;q=0, gzip")]), variants()), Some(("gzip", 3)));
+        // Only the variants next to the file count
+        assert_eq!(preferred(&headers(&[("accept-encoding", "br")]), vec![("gzip", 3)]), None);
+        assert_eq!(preferred(&headers(&[]), variants()), None);
+    }
+
+    // Through the handler, with the files compiled in
+    #[cfg(feature = "embed")]
+    #[tokio::test]
+    async fn serves_embedded_ranges() {
+        use axum::body::to_bytes;
+
+        const PATH: &str = "/css/electric-eclipse/main.css";
+        let get = |range: &'static str| Request::get(PATH).header(RANGE, range).body(Body::empty()).unwrap();
+        let length = embedded_file(PATH.trim_start_matches('/')).unwrap().data.len();
+
+        let response = serve(get("bytes=0-")).await;
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.headers()[CONTENT_RANGE], format!("bytes 0-{}/{}", length - 1, length).as_str());
+
+        let response = serve(get("bytes=-10")).await;
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(to_bytes(response.into_body(), usize::MAX).await.unwrap().len(), 10);
+
+        let response = serve(get("bytes=999999999-")).await;
+        assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(response.headers()[CONTENT_RANGE], format!("bytes */{}", length).as_str());
+
+        let response = serve(get("bytes=0-9,20-29")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(to_bytes(response.into_body(), usize::MAX).await.unwrap().len(), length);
+    }
+}
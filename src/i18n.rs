@@ -0,0 +1,417 @@
+use crate::csrf::cookie_value;
+use axum::{
+    extract::{FromRequestParts, OriginalUri, Request},
+    http::{
+        HeaderMap, HeaderValue, Uri,
+        header::{ACCEPT_LANGUAGE, CONTENT_TYPE, SET_COOKIE, VARY},
+        request::Parts,
+    },
+    middleware::Next,
+    response::Response,
+};
+use chrono::{Datelike, NaiveDate};
+use std::{collections::HashMap, convert::Infallible, fmt, sync::OnceLock};
+
+// Interface languages: translation catalogs, locale negotiation and localized URLs.
+// Every page is available under a language prefix (/es/about, /en/about); unprefixed
+// URLs use the `lang` cookie, then Accept-Language, then the default language.
+
+pub const COOKIE_NAME: &str = "lang";
+
+/// Remembers the language picked through a prefixed URL for a year
+const COOKIE_MAX_AGE: u64 = 365 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Locale {
+    Es,
+    En,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::Es, Locale::En];
+
+    /// Used when nothing else matches; its catalog and data files are the reference
+    pub const DEFAULT: Locale = Locale::Es;
+
+    /// Language code, as used in URLs, `lang` attributes and `hreflang`
+    pub fn code(&self) -> &'static str {
+        match self {
+            Locale::Es => "es",
+            Locale::En => "en",
+        }
+    }
+
+    /// Matches on the primary subtag, so `en-GB` selects English
+    pub fn from_code(code: &str) -> Option<Locale> {
+        let primary = code.split(['-', '_']).next()?.trim();
+        Locale::ALL
+            .into_iter()
+            .find(|locale| locale.code().eq_ignore_ascii_case(primary))
+    }
+
+    /// Open Graph `og:locale`
+    pub fn og_locale(&self) -> &'static str {
+        match self {
+            Locale::Es => "es_ES",
+            Locale::En => "en_US",
+        }
+    }
+
+    /// Shown in the language switcher, in the language itself
+    pub fn native_name(&self) -> &'static str {
+        match self {
+            Locale::Es => "Español",
+            Locale::En => "English",
+        }
+    }
+
+    /// `path` under this language's prefix, e.g. `/en/about`
+    pub fn path(&self, path: &str) -> String {
+        if path == "/" {
+            format!("/{}", self.code())
+        } else {
+            format!("/{}{}", self.code(), path)
+        }
+    }
+
+    /// Translated text for `key`, falling back to the default language and then to the key itself
+    pub fn t(&self, key: &'static str) -> &'static str {
+        let catalogs = CATALOGS.get();
+        [*self, Locale::DEFAULT]
+            .into_iter()
+            .find_map(|locale| catalogs?.get(&locale)?.get(key))
+            .map_or(key, String::as_str)
+    }
+
+    /// Date written out, e.g. "20 de noviembre de 2024" or "November 20, 2024"
+    pub fn format_date(&self, date: NaiveDate) -> String {
+        const MONTHS: [&str; 12] = [
+            "date.january",
+            "date.february",
+            "date.march",
+            "date.april",
+            "date.may",
+            "date.june",
+            "date.july",
+            "date.august",
+            "date.september",
+            "date.october",
+            "date.november",
+            "date.december",
+        ];
+        self.t_with(
+            "date.format",
+            &[
+                ("day", &date.day().to_string()),
+                ("month", self.t(MONTHS[date.month0() as usize])),
+                ("year", &date.year().to_string()),
+            ],
+        )
+    }
+
+    /// Same as [`Locale::t`] with `{name}` placeholders filled in
+    pub fn t_with(&self, key: &'static str, args: &[(&str, &str)]) -> String {
+        args.iter().fold(self.t(key).to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{}}}", name), value)
+        })
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+// Translations are compiled into the binary
+const SOURCES: [(Locale, &str); 2] = [
+    (Locale::Es, include_str!("../locales/es.toml")),
+    (Locale::En, include_str!("../locales/en.toml")),
+];
+
+type Catalog = HashMap<String, String>;
+
+static CATALOGS: OnceLock<HashMap<Locale, Catalog>> = OnceLock::new();
+
+#[derive(Debug)]
+pub enum CatalogError {
+    Parse(Locale, toml::de::Error),
+    NotText(Locale, String),
+    Missing(Locale, Vec<String>),
+}
+
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CatalogError::Parse(locale, err) => write!(f, "invalid {}.toml: {}", locale, err),
+            CatalogError::NotText(locale, key) => write!(f, "{}.toml: {} is not a string", locale, key),
+            CatalogError::Missing(locale, keys) => {
+                write!(f, "{}.toml is missing {}", locale, keys.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for CatalogError {}
+
+/// Parse the catalogs and check that every language has all the keys of the default one
+pub fn load_catalogs() -> Result<(), CatalogError> {
+    let mut catalogs = HashMap::new();
+    for (locale, source) in SOURCES {
+        let table: toml::Table = toml::from_str(source).map_err(|err| CatalogError::Parse(locale, err))?;
+        let mut catalog = Catalog::new();
+        flatten(locale, "", table, &mut catalog)?;
+        catalogs.insert(locale, catalog);
+    }
+
+    let reference = &catalogs[&Locale::DEFAULT];
+    for (locale, catalog) in &catalogs {
+        let mut missing: Vec<String> = reference.keys().filter(|key| !catalog.contains_key(*key)).cloned().collect();
+        if !missing.is_empty() {
+            missing.sort();
+            return Err(CatalogError::Missing(*locale, missing));
+        }
+    }
+
+    // The sources are compiled in, so a repeated call could only produce the same catalogs
+    let _ = CATALOGS.set(catalogs);
+    Ok(())
+}
+
+// Sections become key prefixes: `[nav] about = ...` is `nav.about`
+fn flatten(locale: Locale, prefix: &str, table: toml::Table, catalog: &mut Catalog) -> Result<(), CatalogError> {
+    for (name, value) in table {
+        let key = if prefix.is_empty() { name } else { format!("{}.{}", prefix, name) };
+        match value {
+            toml::Value::String(text) => {
+                catalog.insert(key, text);
+            }
+            toml::Value::Table(table) => flatten(locale, &key, table, catalog)?,
+            _ => return Err(CatalogError::NotText(locale, key)),
+        }
+    }
+    Ok(())
+}
+
+/// Language of the current request and its path without the language prefix,
+/// added to the request by [`negotiate`]
+#[derive(Clone, Debug)]
+pub struct Lang {
+    pub locale: Locale,
+    path: String,
+}
+
+impl Lang {
+    /// Path of the current page in `locale`, for the language switcher
+    pub fn alternate(&self, locale: Locale) -> String {
+        locale.path(&self.path)
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Lang {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        // Without the middleware everything is served in the default language
+        Ok(parts.extensions.get::<Lang>().cloned().unwrap_or_else(|| Lang {
+            locale: Locale::DEFAULT,
+            path: parts.uri.path().to_string(),
+        }))
+    }
+}
+
+/// Middleware wrapping the whole router: strips the language prefix before routing,
+/// so each route is declared once, and picks the language of the response
+pub async fn negotiate(mut request: Request, next: Next) -> Response {
+    let from_cookie = cookie_value(request.headers(), COOKIE_NAME).and_then(Locale::from_code);
+    let prefixed = split_prefix(request.uri().path());
+    let is_prefixed = prefixed.is_some();
+
+    let (locale, path) = match prefixed {
+        Some((locale, rest)) => (locale, rest),
+        None => {
+            let locale = from_cookie
+                .or_else(|| from_accept_language(request.headers()))
+                .unwrap_or(Locale::DEFAULT);
+            (locale, request.uri().path().to_string())
+        }
+    };
+
+    if is_prefixed {
+        let original = request.uri().clone();
+        if let Some(uri) = replace_path(&original, &path) {
+            *request.uri_mut() = uri;
+        }
+        // Keeps the address the client asked for in logs and `OriginalUri`
+        request.extensions_mut().insert(OriginalUri(original));
+    }
+    request.extensions_mut().insert(Lang { locale, path });
+
+    let mut response = next.run(request).await;
+
+    let is_html = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/html"));
+    if !is_prefixed && is_html {
+        // Unprefixed pages depend on the cookie and Accept-Language
+        response
+            .headers_mut()
+            .append(VARY, HeaderValue::from_static("accept-language, cookie"));
+    }
+    if is_prefixed && from_cookie != Some(locale) {
+        let cookie = format!(
+            "{}={}; Path=/; Max-Age={}; SameSite=Lax",
+            COOKIE_NAME,
+            locale.code(),
+            COOKIE_MAX_AGE
+        );
+        if let Ok(value) = cookie.parse() {
+            response.headers_mut().append(SET_COOKIE, value);
+        }
+    }
+    response
+}
+
+// `/en` and `/en/about` are English with paths `/` and `/about`; `/english` has no prefix
+fn split_prefix(path: &str) -> Option<(Locale, String)> {
+    let rest = path.strip_prefix('/')?;
+    let (code, rest) = match rest.split_once('/') {
+        Some((code, rest)) => (code, format!("/{}", rest)),
+        None => (rest, "/".to_string()),
+    };
+    let locale = Locale::ALL.into_iter().find(|locale| locale.code() == code)?;
+    Some((locale, rest))
+}
+
+fn replace_path(uri: &Uri, path: &str) -> Option<Uri> {
+    let path_and_query = match uri.query() {
+        Some(query) => format!("{}?{}", path, query),
+        None => path.to_string(),
+    };
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = Some(path_and_query.parse().ok()?);
+    Uri::from_parts(parts).ok()
+}
+
+// Highest weighted supported language, e.g. `en-US,en;q=0.9,es;q=0.8`
+fn from_accept_language(headers: &HeaderMap) -> Option<Locale> {
+    let header = headers.get(ACCEPT_LANGUAGE)?.to_str().ok()?;
+    header
+        .split(',')
+        .filter_map(|item| {
+            let mut parts = item.split(';');
+            let locale = Locale::from_code(parts.next()?.trim())?;
+            let quality = parts
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+            (quality > 0.0).then_some((locale, quality))
+        })
+        // The first of equally weighted languages wins
+        .fold(None, |best: Option<(Locale, f32)>, (locale, quality)| match best {
+            Some((_, best_quality)) if best_quality >= quality => best,
+            _ => Some((locale, quality)),
+        })
+        .map(|(locale, _)| locale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Body, body::to_bytes, middleware, response::Html, routing::get};
+    use std::future::poll_fn;
+    use tower::{Layer, Service};
+
+    fn accept_language(value: &'static str) -> Option<Locale> {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT_LANGUAGE, HeaderValue::from_static(value));
+        from_accept_language(&headers)
+    }
+
+    #[test]
+    fn accept_language_weights() {
+        assert_eq!(accept_language("en"), Some(Locale::En));
+        assert_eq!(accept_language("es-ES,es;q=0.9,en;q=0.8"), Some(Locale::Es));
+        assert_eq!(accept_language("fr-FR,fr;q=0.9,en;q=0.7,es;q=0.8"), Some(Locale::Es));
+        assert_eq!(accept_language("es;q=0.2, en;q=0.9"), Some(Locale::En));
+        // Regions and case
+        assert_eq!(accept_language("en-GB"), Some(Locale::En));
+        assert_eq!(accept_language("ES-mx"), Some(Locale::Es));
+        // The first of equally weighted languages
+        assert_eq!(accept_language("en, es"), Some(Locale::En));
+        assert_eq!(accept_language("es;q=0.5, en;q=0.5"), Some(Locale::Es));
+        // Refused or unsupported
+        assert_eq!(accept_language("en;q=0"), None);
+        assert_eq!(accept_language("en;q=0, es;q=0.1"), Some(Locale::Es));
+        assert_eq!(accept_language("fr, de"), None);
+        assert_eq!(accept_language("en;q=high"), None);
+        assert_eq!(from_accept_language(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn language_prefixes() {
+        assert_eq!(split_prefix("/es"), Some((Locale::Es, "/".to_string())));
+        assert_eq!(split_prefix("/es/"), Some((Locale::Es, "/".to_string())));
+        assert_eq!(split_prefix("/en/about"), Some((Locale::En, "/about".to_string())));
+        assert_eq!(split_prefix("/en/blog/tags/rust"), Some((Locale::En, "/blog/tags/rust".to_string())));
+        assert_eq!(split_prefix("/esx"), None);
+        assert_eq!(split_prefix("/english/about"), None);
+        assert_eq!(split_prefix("/EN/about"), None);
+        assert_eq!(split_prefix("/about/en"), None);
+        assert_eq!(split_prefix("/"), None);
+    }
+
+    // Answers with the negotiated language and the path the router saw; wrapped around
+    // the router like in `main`, so the prefix is gone before routing
+    fn app() -> impl Service<Request, Response = Response, Error = Infallible> {
+        let echo = |lang: Lang, uri: Uri| async move { Html(format!("{} {}", lang.locale, uri.path())) };
+        let router = Router::new().route("/", get(echo)).route("/about", get(echo));
+        middleware::from_fn(negotiate).layer(router)
+    }
+
+    async fn get_with(path: &str, headers: &[(&str, &str)]) -> (String, HeaderMap) {
+        let mut request = Request::get(path);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let mut app = app();
+        let Ok(()) = poll_fn(|cx| app.poll_ready(cx)).await;
+        let Ok(response) = app.call(request.body(Body::empty()).unwrap()).await;
+        let headers = response.headers().clone();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (String::from_utf8(body.to_vec()).unwrap(), headers)
+    }
+
+    #[tokio::test]
+    async fn prefix_then_cookie_then_header() {
+        let english = ("accept-language", "en-US,en;q=0.9");
+        let spanish_cookie = ("cookie", "lang=es");
+
+        assert_eq!(get_with("/about", &[]).await.0, "es /about");
+        assert_eq!(get_with("/about", &[english]).await.0, "en /about");
+        assert_eq!(get_with("/about", &[english, spanish_cookie]).await.0, "es /about");
+        assert_eq!(get_with("/en/about", &[spanish_cookie]).await.0, "en /about");
+        assert_eq!(get_with("/es", &[english, ("cookie", "lang=en")]).await.0, "es /");
+        // An unknown cookie value falls through to the header
+        assert_eq!(get_with("/about", &[english, ("cookie", "lang=fr")]).await.0, "en /about");
+    }
+
+    #[tokio::test]
+    async fn prefixed_pages_remember_the_language() {
+        let (_, headers) = get_with("/en/about", &[]).await;
+        let cookie = headers[SET_COOKIE].to_str().unwrap();
+        assert!(cookie.starts_with("lang=en;"), "{}", cookie);
+        assert!(!headers.contains_key(VARY));
+        assert_eq!(get_with("/en", &[]).await.0, "en /");
+
+        // Already remembered
+        let (_, headers) = get_with("/en/about", &[("cookie", "lang=en")]).await;
+        assert!(!headers.contains_key(SET_COOKIE));
+
+        // Unprefixed pages depend on what the client sent
+        let (_, headers) = get_with("/about", &[]).await;
+        assert!(!headers.contains_key(SET_COOKIE));
+        assert_eq!(headers[VARY], "accept-language, cookie");
+    }
+}
@@ -1,83 +1,86 @@
-/// Macro para generar handlers de páginas con renderizado dual y logging de desarrollo
-/// 
+/// Macro para generar handlers de páginas con renderizado dual
+///
 /// Genera una función handler que:
-/// - Para requests htmx: devuelve solo el contenido de la vista
-/// - Para requests normales: devuelve página completa con layout
-/// - En modo debug: loggea información detallada del request con colores y emojis
+/// - Para requests htmx: devuelve solo el contenido de la vista, con su `<title>`
+/// - Para requests normales: devuelve página completa con layout y los metadatos de la vista (`PageMeta`)
+/// - Pasa a la vista la versión actual del contenido de `static/data/` (ver `ContentStore`)
+///   y el idioma de la petición (ver `i18n::negotiate`)
+/// - Acepta opcionalmente una expresión de renderizado propia, con acceso al contenido,
+///   al estado, al token CSRF y al idioma, para vistas que necesitan algo más (p. ej. formularios)
+/// - Acepta extractores adicionales entre corchetes (`[slug: Path<String>]`); en ese caso
+///   la expresión devuelve `Option<Page>` y `None` responde con un 404
+/// - Reutiliza el HTML ya renderizado para la misma ruta, parámetros leídos por los extractores,
+///   modo, idioma y versión del contenido (ver `render_cache`), salvo en las vistas marcadas con
+///   `Page::uncached` o si la petición lleva parámetros que el handler no lee
 #[macro_export]
 macro_rules! generate_page_handler {
     ($handler_name:ident, $section:literal, $view_module:ident) => {
+        $crate::generate_page_handler!($handler_name, $section, |site, state, csrf, locale| {
+            $crate::views::$view_module::render(&site, locale)
+        });
+    };
+    ($handler_name:ident, $section:literal, |$site:ident, $state:ident, $csrf:ident, $locale:ident| $render:expr) => {
+        $crate::generate_page_handler!($handler_name, $section, [], |$site, $state, $csrf, $locale| {
+            Some($render)
+        });
+    };
+    ($handler_name:ident, $section:literal, [$($arg:ident: $arg_ty:ty),* $(,)?], |$site:ident, $state:ident, $csrf:ident, $locale:ident| $render:expr) => {
         pub async fn $handler_name(
-            _uri: axum::http::Uri,
-            _method: axum::http::Method,
-            _headers: axum::http::HeaderMap,
+            axum::extract::State($state): axum::extract::State<$crate::state::AppState>,
             axum_htmx::HxRequest(is_htmx): axum_htmx::HxRequest,
-        ) -> impl axum::response::IntoResponse {
-            // Logging only in debug mode (development)
-            #[cfg(debug_assertions)]
-            {
-                use std::time::{SystemTime, UNIX_EPOCH};
-                
-                let timestamp = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_secs();
-                
-                let user_agent = _headers
-                    .get("user-agent")
-                    .and_then(|v| v.to_str().ok())
-                    .unwrap_or("Unknown");
-                
-                let referer = _headers
-                    .get("referer")
-                    .and_then(|v| v.to_str().ok())
-                    .unwrap_or("-");
-                
-                let request_type = if is_htmx { "HTMX" } else { "FULL" };
-                let request_source = if user_agent.contains("htmx") {
-                    "🔄"
-                } else if user_agent.contains("Mozilla") {
-                    "🌐"
-                } else {
-                    "🤖"
-                };
-                
-                // Enhanced logging format: [timestamp] method uri [type] source - referer
-                println!(
-                    "\x1b[36m[{}]\x1b[0m \x1b[32m{}\x1b[0m \x1b[1m{}\x1b[0m \x1b[33m[{}]\x1b[0m {} - \x1b[90m{}\x1b[0m",
-                    timestamp,
-                    _method,
-                    _uri,
-                    request_type,
-                    request_source,
-                    if referer == "-" { "direct" } else { referer }
-                );
-                
-                // Additional debug info for htmx requests
-                if is_htmx {
-                    if let Some(target) = _headers.get("hx-target").and_then(|v| v.to_str().ok()) {
-                        println!("  \x1b[90m└─ Target: {}\x1b[0m", target);
-                    }
-                    if let Some(trigger) = _headers.get("hx-trigger").and_then(|v| v.to_str().ok()) {
-                        println!("  \x1b[90m└─ Trigger: {}\x1b[0m", trigger);
-                    }
-                }
-            }
-            
+            key: $crate::render_cache::Key,
+            $csrf: $crate::csrf::CsrfToken,
+            lang: $crate::i18n::Lang,
+            $($arg: $arg_ty,)*
+        ) -> axum::response::Response {
+            use axum::response::IntoResponse;
+
             fn into_html_response(markup: maud::Markup) -> axum::response::Html<String> {
                 axum::response::Html(markup.into_string())
             }
-            
-            let content = crate::views::$view_module::render();
-            
-            if is_htmx {
-                into_html_response(content)
-            } else {
-                into_html_response(crate::views::layout::render_page_with_content(
-                    $section,
-                    content,
-                ))
+
+            let $locale = lang.locale;
+            let key = key.read(&[$($crate::render_cache::KeyPart::query(&$arg)),*]);
+            // Read before the content, so a reload in between can't be cached as the old version
+            let version = $state.content.version();
+            if let Some(response) = key.as_ref().and_then(|key| $state.render_cache.get(key, version, &$csrf)) {
+                return response;
+            }
+
+            let $site = $state.content.current($locale);
+            let page: Option<$crate::views::page::Page> = $render;
+
+            match page {
+                Some(page) if is_htmx => {
+                    let key = key.filter(|_| page.cacheable);
+                    let html = $crate::views::layout::render_partial(&$state.config, page).into_string();
+                    $state.render_cache.store(key, version, html, &$csrf)
+                }
+                Some(page) => {
+                    // Shared pages get the visitor's token when they are sent
+                    let key = key.filter(|_| page.cacheable);
+                    let token = if key.is_some() { $crate::csrf::CsrfToken::placeholder() } else { $csrf.clone() };
+                    let html = $crate::views::layout::render_page_with_content(
+                        &$state.config,
+                        &$site,
+                        &token,
+                        &lang,
+                        $section,
+                        page,
+                    )
+                    .into_string();
+                    $state.render_cache.store(key, version, html, &$csrf)
+                }
+                None if is_htmx => $crate::views::error_view::htmx_error(
+                    axum::http::StatusCode::NOT_FOUND,
+                    $crate::views::error_view::not_found_content($locale),
+                ),
+                None => (
+                    axum::http::StatusCode::NOT_FOUND,
+                    into_html_response($crate::views::error_view::not_found(&$state.config, $locale)),
+                )
+                    .into_response(),
             }
         }
     };
-}
\ No newline at end of file
+}
@@ -1,98 +1,234 @@
 use axum::{
+    extract::{Path, Request, State},
+    middleware,
     http::{
-        header::{CACHE_CONTROL, CONTENT_TYPE, HeaderName},
-        HeaderValue,
+        header::{CONTENT_TYPE, HeaderName, LAST_MODIFIED},
+        HeaderValue, StatusCode,
     },
-    response::{Html, IntoResponse},
+    response::{Html, IntoResponse, Response},
     routing::{get, post},
     Router,
 };
 use axum_htmx::AutoVaryLayer;
+use i18n::Lang;
 use maud::Markup;
-use std::fs;
-use tower::ServiceBuilder;
-use tower_http::{services::ServeDir, set_header::SetResponseHeaderLayer};
+use std::{convert::Infallible, future::IntoFuture, net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
+use tokio::sync::Notify;
+use tower::{Layer, Service, ServiceBuilder};
+use tower_http::{compression::CompressionLayer, services::ServeDir, set_header::SetResponseHeaderLayer};
 
+mod assets;
+mod auth;
+mod blog;
+mod conditional;
+mod config;
+mod content;
 mod controllers;
+mod csrf;
+mod export;
+mod feed;
+mod files;
+mod i18n;
 mod macros;
+mod messages;
+mod notifier;
+mod og;
+mod rate_limit;
+mod render_cache;
 mod routes;
+mod search;
+mod sitemap;
+mod spam;
+mod state;
+mod telemetry;
+mod validation;
 mod views;
+use auth::AdminCredentials;
+use config::{Command, Config};
+use content::{Content, ContentStore};
+use csrf::Csrf;
+use messages::MessageStore;
+use notifier::Notifiers;
+use rate_limit::RateLimiter;
+use render_cache::RenderCache;
+use sha2::{Digest, Sha256};
+use spam::FormTokens;
 use routes::Route;
+use sitemap::SitemapCache;
+use state::AppState;
 
-// Helper function to read JSON-LD files
-fn read_json_ld_file(filename: &str) -> String {
-    // Try multiple possible paths for JSON-LD files
-    let possible_paths = [
-        format!("static/data/{}", filename),   // Local development
-        format!("/static/data/{}", filename),  // Docker container
-        format!("./static/data/{}", filename), // Alternative local
-    ];
-
-    for path in &possible_paths {
-        if let Ok(content) = fs::read_to_string(path) {
-            return content;
+// How often the data directory is checked for changes
+const CONTENT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+// Contact form submissions allowed per IP: a burst of 5, then one more per minute
+const CONTACT_BURST: u32 = 5;
+const CONTACT_REFILL: Duration = Duration::from_secs(60);
+
+// Admin requests per IP, including failed logins: a burst of 30, then one every 2 seconds
+const ADMIN_BURST: u32 = 30;
+const ADMIN_REFILL: Duration = Duration::from_secs(2);
+
+/// Key for signing form and CSRF tokens, derived from `secret_key`. Without it a
+/// random key is used and forms rendered before a restart stop being accepted.
+fn load_secret_key(config: &Config) -> [u8; 32] {
+    match &config.secret_key {
+        Some(secret) => Sha256::digest(secret.expose().as_bytes()).into(),
+        None => {
+            tracing::warn!("SECRET_KEY not set, using a random key (open forms are invalidated on restart)");
+            let mut key = [0u8; 32];
+            rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut key);
+            key
         }
     }
+}
 
-    "{}".to_string()
+// Helper function to convert Maud Markup to Html response
+fn into_html_response(markup: Markup) -> Html<String> {
+    Html(markup.into_string())
 }
 
-// Public function to get structured data
-pub fn get_website_json_ld() -> String {
-    read_json_ld_file("website.json")
+async fn json_ld_website(State(state): State<AppState>, lang: Lang) -> impl IntoResponse {
+    let content = state.content.current(lang.locale);
+    let json_ld = state.config.expand_json(&content.website_json_ld);
+    (
+        [
+            (CONTENT_TYPE, HeaderValue::from_static("application/json")),
+            (LAST_MODIFIED, conditional::http_date(content.loaded_at)),
+        ],
+        json_ld,
+    )
 }
 
-pub fn get_person_json_ld() -> String {
-    read_json_ld_file("person.json")
+async fn json_ld_person(State(state): State<AppState>, lang: Lang) -> impl IntoResponse {
+    let content = state.content.current(lang.locale);
+    let json_ld = state.config.expand_json(&content.person_json_ld);
+    (
+        [
+            (CONTENT_TYPE, HeaderValue::from_static("application/json")),
+            (LAST_MODIFIED, conditional::http_date(content.loaded_at)),
+        ],
+        json_ld,
+    )
 }
 
-// Helper function to convert Maud Markup to Html response
-fn into_html_response(markup: Markup) -> Html<String> {
-    Html(markup.into_string())
+async fn serve_robots(State(state): State<AppState>) -> impl IntoResponse {
+    ([(CONTENT_TYPE, "text/plain")], state.config.expand(include_str!("../static/robots.txt")))
 }
 
-async fn json_ld_website() -> impl IntoResponse {
-    ([(CONTENT_TYPE, "application/json")], get_website_json_ld())
+async fn serve_sitemap(State(state): State<AppState>) -> impl IntoResponse {
+    let sitemap = state.sitemap.get(&state.config, &state.content);
+    ([(CONTENT_TYPE, "application/xml")], sitemap.root().to_string())
 }
 
-async fn json_ld_person() -> impl IntoResponse {
-    ([(CONTENT_TYPE, "application/json")], get_person_json_ld())
+// Parts of a sitemap index, `/sitemaps/2.xml`
+async fn serve_sitemap_part(
+    State(state): State<AppState>,
+    Path(file): Path<String>,
+    lang: Lang,
+) -> Response {
+    let sitemap = state.sitemap.get(&state.config, &state.content);
+    let part = file
+        .strip_suffix(".xml")
+        .and_then(|number| number.parse().ok())
+        .and_then(|number| sitemap.part(number));
+    match part {
+        Some(xml) => ([(CONTENT_TYPE, "application/xml")], xml.to_string()).into_response(),
+        None => (StatusCode::NOT_FOUND, not_found(State(state), lang).await).into_response(),
+    }
 }
 
-async fn serve_robots() -> impl IntoResponse {
-    ([(CONTENT_TYPE, "text/plain")], include_str!("../static/robots.txt"))
+async fn serve_rss(State(state): State<AppState>, lang: Lang) -> impl IntoResponse {
+    let content = state.content.current(lang.locale);
+    (
+        [(CONTENT_TYPE, "application/rss+xml; charset=utf-8")],
+        feed::rss(&state.config, &content, lang.locale),
+    )
 }
 
-async fn serve_sitemap() -> impl IntoResponse {
-    ([(CONTENT_TYPE, "application/xml")], include_str!("../static/sitemap.xml"))
+async fn serve_atom(State(state): State<AppState>, lang: Lang) -> impl IntoResponse {
+    let content = state.content.current(lang.locale);
+    (
+        [(CONTENT_TYPE, "application/atom+xml; charset=utf-8")],
+        feed::atom(&state.config, &content, lang.locale),
+    )
 }
 
-async fn not_found() -> impl IntoResponse {
-    into_html_response(views::error_view::not_found())
+async fn not_found(State(state): State<AppState>, lang: Lang) -> impl IntoResponse {
+    (
+        StatusCode::NOT_FOUND,
+        into_html_response(views::error_view::not_found(&state.config, lang.locale)),
+    )
 }
 
-#[tokio::main]
-async fn main() {
-    // Create static file service with cache headers
-    let static_service = ServiceBuilder::new()
-        .layer(SetResponseHeaderLayer::overriding(
-            CACHE_CONTROL,
-            HeaderValue::from_static("public, max-age=31536000, immutable"),
-        ))
-        .service(ServeDir::new("static"));
+/// Every route and middleware. `i18n::negotiate` wraps the router, so it runs before routing
+fn app(
+    state: AppState,
+) -> impl Service<Request, Response = Response, Error = Infallible, Future: Send> + Clone + Send + 'static {
+    let contact_limiter = Arc::new(RateLimiter::new(CONTACT_BURST, CONTACT_REFILL));
+    let admin_limiter = Arc::new(RateLimiter::new(ADMIN_BURST, ADMIN_REFILL));
+
+    // Admin inbox, protected with HTTP Basic authentication. The rate limit goes
+    // outside it, so failed logins count too
+    let admin_routes = Router::new()
+        .route("/", get(controllers::admin::inbox))
+        .route("/{id}", get(controllers::admin::message))
+        .route("/{id}/handled", post(controllers::admin::mark_handled))
+        .route("/{id}/delete", post(controllers::admin::delete))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth::require_admin))
+        .route_layer(middleware::from_fn_with_state((state.clone(), admin_limiter), rate_limit::limit));
+
+    // Static files, cached for a year under their fingerprinted names. `.br`, `.zst`
+    // and `.gz` files next to a file are sent instead of compressing it on the fly.
+    // The data directory inside it is left out
+    let static_service = if files::is_embedded() {
+        Router::new().fallback(files::serve)
+    } else {
+        Router::new().fallback_service(
+            ServeDir::new(&state.config.static_dir)
+                .precompressed_br()
+                .precompressed_zstd()
+                .precompressed_gzip(),
+        )
+    }
+    .layer(middleware::from_fn_with_state(
+        assets::PrivateDir::new(&state.config.static_dir, &state.config.data_dir),
+        assets::serve,
+    ));
 
     // Build our application with routes and security headers
     let app = Router::new()
-        .route(Route::Home.path(), get(controllers::home::handler))
-        .route(Route::About.path(), get(controllers::about::handler))
-        .route(Route::Experience.path(), get(controllers::experience::handler))
-        .route(Route::Projects.path(), get(controllers::projects::handler))
-        .route(Route::Contact.path(), get(controllers::contact::handler))
-        .route(Route::Contact.path(), post(controllers::contact::contact_submit))
+        .route(&Route::Home.path(), get(controllers::home::handler))
+        .route(&Route::About.path(), get(controllers::about::handler))
+        .route(&Route::Experience.path(), get(controllers::experience::handler))
+        .route(&Route::Projects.path(), get(controllers::projects::handler))
+        .route(Route::PROJECT_DETAIL_PATTERN, get(controllers::projects::detail))
+        .route(Route::PROJECT_DEMO_PATTERN, get(controllers::projects::demo))
+        .route(Route::PROJECT_CODE_PATTERN, get(controllers::projects::code))
+        .route(&Route::Blog.path(), get(controllers::blog::index))
+        .route("/blog/{slug}", get(controllers::blog::post))
+        .route("/blog/tags/{tag}", get(controllers::blog::tag))
+        .route(&Route::Search.path(), get(controllers::search::search))
+        .route(&Route::Contact.path(), get(controllers::contact::handler))
+        .route(
+            &Route::Contact.path(),
+            post(controllers::contact::contact_submit)
+                .layer(middleware::from_fn_with_state((state.clone(), contact_limiter), rate_limit::limit)),
+        )
         .route("/api/json-ld/website", get(json_ld_website))
         .route("/api/json-ld/person", get(json_ld_person))
+        .route("/healthz", get(controllers::health::healthz))
+        .route("/readyz", get(controllers::health::readyz))
+        .route("/metrics", get(telemetry::serve_metrics))
         .route("/robots.txt", get(serve_robots))
         .route("/sitemap.xml", get(serve_sitemap))
+        .route("/sitemaps/{file}", get(serve_sitemap_part))
+        .route(feed::RSS_PATH, get(serve_rss))
+        .route(feed::ATOM_PATH, get(serve_atom))
+        .route("/og/{file}", get(og::serve))
+        .route("/og/pages/{file}", get(og::serve_page))
+        .nest(&Route::AdminMessages.path(), admin_routes)
+        // Every route above gets a CSRF token; static files don't need one
+        .layer(middleware::from_fn_with_state(state.clone(), csrf::protect))
         .nest_service("/static", static_service)
         .layer(ServiceBuilder::new()
             // Security headers
@@ -118,18 +254,229 @@ async fn main() {
             ))
         )
         .layer(AutoVaryLayer)
-        .fallback(not_found);
+        .fallback(not_found)
+        // gzip, brotli or zstd as the client accepts; ETags are computed from the
+        // compressed body, so every encoding is its own variant
+        .layer(CompressionLayer::new())
+        .layer(middleware::from_fn(conditional::conditional_get))
+        .layer(middleware::from_fn(telemetry::track_metrics));
 
-    // Configure bind address based on build mode
-    let bind_addr = if cfg!(debug_assertions) {
-        "127.0.0.1:3000" // Development: localhost only
-    } else {
-        "0.0.0.0:3000" // Production: all interfaces
+    // Request IDs, status and latency for every route, static files and 404s included
+    let app = telemetry::trace_requests(app).with_state(state);
+
+    // Runs before routing, so /en/about is routed as /about
+    middleware::from_fn(i18n::negotiate).layer(app)
+}
+
+#[tokio::main]
+async fn main() {
+    // Defaults < config file < environment < command line flags
+    let (config, command) = match Config::load() {
+        Ok((config, command)) => (Arc::new(config), command),
+        Err(err) => {
+            eprintln!("Failed to load configuration: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    if let Command::Healthcheck = command {
+        let healthy = controllers::health::probe(&config).await;
+        std::process::exit(if healthy { 0 } else { 1 });
+    }
+
+    telemetry::init(&config);
+    let metrics = match telemetry::install_metrics_recorder() {
+        Ok(handle) => handle,
+        Err(err) => {
+            eprintln!("Failed to set up metrics: {}", err);
+            std::process::exit(1);
+        }
+    };
+    tracing::info!(
+        base_url = %config.base_url,
+        static_dir = %config.static_dir.display(),
+        data_dir = %config.data_dir.display(),
+        log_level = %config.log_level,
+        "Starting"
+    );
+
+    // Interface translations, compiled in from locales/
+    if let Err(err) = i18n::load_catalogs() {
+        tracing::error!(error = %err, "Failed to load translations");
+        std::process::exit(1);
+    }
+
+    // Built with the `embed` feature, the binary carries its own copy of static/
+    if files::init(&config.static_dir) {
+        tracing::info!("{} not found, serving the static files embedded in the binary", config.static_dir.display());
+    }
+
+    // Fingerprinted names of the static files, for `assets::asset_url`
+    if let Err(err) = assets::load_manifest(&config.static_dir, &config.data_dir) {
+        tracing::error!(error = %err, "Failed to read static files from {}", config.static_dir.display());
+        std::process::exit(1);
+    }
+
+    // Load site content (profile, experience, projects, skills, JSON-LD) for every language
+    let content = match Content::load_all(&config.data_dir) {
+        Ok(content) => content,
+        Err(err) => {
+            tracing::error!(error = %err, "Failed to load site content from {}", config.data_dir.display());
+            std::process::exit(1);
+        }
     };
+    let content = Arc::new(ContentStore::new(config.data_dir.clone(), content));
+    content.clone().watch(CONTENT_POLL_INTERVAL);
+
+    // Contact messages database; an export renders no messages, so it gets a throwaway one
+    let messages_db = match command {
+        Command::Export { .. } => PathBuf::from(":memory:"),
+        _ => config.messages_db.clone(),
+    };
+    let messages = match MessageStore::open(&messages_db) {
+        Ok(store) => store,
+        Err(err) => {
+            tracing::error!(error = %err, "Failed to open messages database {}", messages_db.display());
+            std::process::exit(1);
+        }
+    };
+
+    // Where new contact messages are delivered (SMTP, webhook, log file or stdout)
+    let notifiers = match Notifiers::from_config(&config.notify) {
+        Ok(notifiers) => notifiers,
+        Err(err) => {
+            tracing::error!(error = %err, "Failed to configure notifiers");
+            std::process::exit(1);
+        }
+    };
+    tracing::info!("Contact notifications: {}", notifiers.names().join(", "));
+
+    let admin = AdminCredentials::from_config(&config).map(Arc::new);
+    if admin.is_none() {
+        tracing::info!("ADMIN_PASSWORD not set, admin pages are disabled");
+    }
+
+    let secret_key = load_secret_key(&config);
+    let form_tokens = Arc::new(FormTokens::new(secret_key));
+    let csrf = Arc::new(Csrf::new(secret_key));
+
+    let state = AppState {
+        config: config.clone(),
+        content,
+        messages,
+        notifiers,
+        form_tokens,
+        csrf,
+        sitemap: Arc::new(SitemapCache::default()),
+        render_cache: Arc::new(RenderCache::default()),
+        metrics,
+        admin,
+    };
+
+    let content = state.content.clone();
+    let app = app(state);
+
+    if let Command::Export { out } = &command {
+        match export::run(app, &config, &content, out).await {
+            Ok(count) => {
+                tracing::info!("Exported {} files to {}", count, out.display());
+                std::process::exit(0);
+            }
+            Err(err) => {
+                tracing::error!(error = %err, "Export failed");
+                std::process::exit(1);
+            }
+        }
+    }
 
     // Run the server
-    let listener = tokio::net::TcpListener::bind(bind_addr).await.unwrap();
+    let bind_addr = config.bind_addr();
+    let listener = match tokio::net::TcpListener::bind(bind_addr).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            tracing::error!(error = %err, "Failed to bind {}", bind_addr);
+            std::process::exit(1);
+        }
+    };
+
+    tracing::info!("Server running on http://{}", bind_addr);
+
+    // On SIGTERM/SIGINT stop accepting connections and let open requests finish,
+    // up to the configured drain timeout
+    let shutdown = Arc::new(Notify::new());
+    let mut server = tokio::spawn(
+        axum::serve(
+            listener,
+            axum::ServiceExt::<axum::extract::Request>::into_make_service_with_connect_info::<SocketAddr>(app),
+        )
+            .with_graceful_shutdown({
+                let shutdown = shutdown.clone();
+                async move { shutdown.notified().await }
+            })
+            .into_future(),
+    );
+
+    tokio::select! {
+        result = &mut server => {
+            report_server_exit(result);
+            return;
+        }
+        () = shutdown_signal() => {}
+    }
+
+    tracing::info!(
+        "Shutting down, waiting up to {}s for open requests",
+        config.shutdown_timeout.as_secs()
+    );
+    shutdown.notify_one();
+    match tokio::time::timeout(config.shutdown_timeout, &mut server).await {
+        Ok(result) => report_server_exit(result),
+        Err(_) => {
+            tracing::warn!("Drain timeout reached, closing remaining connections");
+            server.abort();
+        }
+    }
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            tracing::error!(error = %err, "Failed to listen for Ctrl+C");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(err) => {
+                tracing::error!(error = %err, "Failed to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => {}
+        () = terminate => {}
+    }
+}
 
-    println!("Server running on http://{}", bind_addr);
-    axum::serve(listener, app).await.unwrap();
+fn report_server_exit(result: Result<std::io::Result<()>, tokio::task::JoinError>) {
+    match result {
+        Ok(Ok(())) => tracing::info!("Server stopped"),
+        Ok(Err(err)) => {
+            tracing::error!(error = %err, "Server error");
+            std::process::exit(1);
+        }
+        Err(err) => {
+            tracing::error!(error = %err, "Server task failed");
+            std::process::exit(1);
+        }
+    }
 }
@@ -0,0 +1,218 @@
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::Serialize;
+use std::{
+    fmt,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+// Contact form submissions persisted in an embedded SQLite database
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageStatus {
+    New,
+    Handled,
+}
+
+impl MessageStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MessageStatus::New => "new",
+            MessageStatus::Handled => "handled",
+        }
+    }
+
+    fn from_db(value: &str) -> Self {
+        match value {
+            "handled" => MessageStatus::Handled,
+            _ => MessageStatus::New,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NewMessage {
+    pub name: String,
+    pub email: String,
+    pub message: String,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StoredMessage {
+    pub id: i64,
+    pub name: String,
+    pub email: String,
+    pub message: String,
+    pub created_at: DateTime<Utc>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub status: MessageStatus,
+}
+
+#[derive(Debug)]
+pub enum StoreError {
+    Database(rusqlite::Error),
+    Task(tokio::task::JoinError),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::Database(err) => write!(f, "database error: {}", err),
+            StoreError::Task(err) => write!(f, "database task failed: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl From<rusqlite::Error> for StoreError {
+    fn from(err: rusqlite::Error) -> Self {
+        StoreError::Database(err)
+    }
+}
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS messages (
+        id         INTEGER PRIMARY KEY AUTOINCREMENT,
+        name       TEXT NOT NULL,
+        email      TEXT NOT NULL,
+        message    TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        ip         TEXT,
+        user_agent TEXT,
+        status     TEXT NOT NULL DEFAULT 'new'
+    );
+    CREATE INDEX IF NOT EXISTS messages_created_at ON messages (created_at);
+";
+
+const COLUMNS: &str = "id, name, email, message, created_at, ip, user_agent, status";
+
+/// Handle to the messages database, cheap to clone.
+///
+/// SQLite calls are blocking, so every query runs on the blocking thread pool.
+#[derive(Clone)]
+pub struct MessageStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl MessageStore {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StoreError> {
+        if let Some(parent) = path.as_ref().parent() {
+            // Let SQLite report the error if the directory can't be created
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let conn = Connection::open(path)?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(MessageStore {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    pub async fn insert(&self, message: NewMessage) -> Result<i64, StoreError> {
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT INTO messages (name, email, message, created_at, ip, user_agent, status)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    message.name,
+                    message.email,
+                    message.message,
+                    Utc::now(),
+                    message.ip,
+                    message.user_agent,
+                    MessageStatus::New.as_str(),
+                ],
+            )?;
+            Ok(conn.last_insert_rowid())
+        })
+        .await
+    }
+
+    /// All messages, newest first
+    pub async fn list(&self) -> Result<Vec<StoredMessage>, StoreError> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM messages ORDER BY created_at DESC, id DESC",
+                COLUMNS
+            ))?;
+            let rows = stmt.query_map([], row_to_message)?;
+            rows.collect::<Result<Vec<_>, _>>().map_err(StoreError::from)
+        })
+        .await
+    }
+
+    pub async fn get(&self, id: i64) -> Result<Option<StoredMessage>, StoreError> {
+        self.with_conn(move |conn| {
+            conn.query_row(
+                &format!("SELECT {} FROM messages WHERE id = ?1", COLUMNS),
+                [id],
+                row_to_message,
+            )
+            .optional()
+            .map_err(StoreError::from)
+        })
+        .await
+    }
+
+    /// Returns false when no message has the given id
+    pub async fn set_status(&self, id: i64, status: MessageStatus) -> Result<bool, StoreError> {
+        self.with_conn(move |conn| {
+            let updated = conn.execute(
+                "UPDATE messages SET status = ?1 WHERE id = ?2",
+                params![status.as_str(), id],
+            )?;
+            Ok(updated > 0)
+        })
+        .await
+    }
+
+    /// Returns false when no message has the given id
+    pub async fn delete(&self, id: i64) -> Result<bool, StoreError> {
+        self.with_conn(move |conn| {
+            let deleted = conn.execute("DELETE FROM messages WHERE id = ?1", [id])?;
+            Ok(deleted > 0)
+        })
+        .await
+    }
+
+    /// Cheap query to check the database is usable
+    pub async fn ping(&self) -> Result<(), StoreError> {
+        self.with_conn(|conn| {
+            conn.query_row("SELECT 1", [], |_| Ok(()))?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn with_conn<T, F>(&self, f: F) -> Result<T, StoreError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T, StoreError> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            f(&conn)
+        })
+        .await
+        .map_err(StoreError::Task)?
+    }
+}
+
+fn row_to_message(row: &rusqlite::Row<'_>) -> rusqlite::Result<StoredMessage> {
+    let status: String = row.get(7)?;
+    Ok(StoredMessage {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        email: row.get(2)?,
+        message: row.get(3)?,
+        created_at: row.get(4)?,
+        ip: row.get(5)?,
+        user_agent: row.get(6)?,
+        status: MessageStatus::from_db(&status),
+    })
+}
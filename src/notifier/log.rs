@@ -0,0 +1,68 @@
+use super::{Notifier, NotifyError, NotifyFuture, format_message};
+use crate::messages::NewMessage;
+use chrono::Utc;
+use std::path::PathBuf;
+use tokio::io::AsyncWriteExt;
+
+/// Development notifier: prints messages to stdout or appends them to a file
+pub struct LogNotifier {
+    path: Option<PathBuf>,
+}
+
+impl LogNotifier {
+    pub fn stdout() -> Self {
+        LogNotifier { path: None }
+    }
+
+    pub fn file(path: impl Into<PathBuf>) -> Self {
+        LogNotifier {
+            path: Some(path.into()),
+        }
+    }
+}
+
+impl Notifier for LogNotifier {
+    fn name(&self) -> &'static str {
+        if self.path.is_some() { "file" } else { "stdout" }
+    }
+
+    fn notify<'a>(&'a self, message: &'a NewMessage) -> NotifyFuture<'a> {
+        Box::pin(async move {
+            let entry = format!(
+                "----- {} -----\n{}",
+                Utc::now().to_rfc3339(),
+                format_message(message)
+            );
+
+            match &self.path {
+                Some(path) => {
+                    let mut file = tokio::fs::OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(path)
+                        .await
+                        .map_err(NotifyError::Io)?;
+                    file.write_all(entry.as_bytes()).await.map_err(NotifyError::Io)
+                }
+                None => {
+                    println!("{}", entry);
+                    Ok(())
+                }
+            }
+        })
+    }
+
+    fn check(&self) -> NotifyFuture<'_> {
+        Box::pin(async move {
+            if let Some(path) = &self.path {
+                tokio::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .await
+                    .map_err(NotifyError::Io)?;
+            }
+            Ok(())
+        })
+    }
+}
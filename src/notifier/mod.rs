@@ -0,0 +1,149 @@
+use crate::config::NotifyConfig;
+use crate::messages::NewMessage;
+use std::{fmt, future::Future, pin::Pin, sync::Arc, time::Duration};
+
+mod log;
+mod smtp;
+mod webhook;
+
+pub use log::LogNotifier;
+pub use smtp::SmtpNotifier;
+pub use webhook::WebhookNotifier;
+
+// Delivery of contact form submissions to the site owner
+
+pub type NotifyFuture<'a> = Pin<Box<dyn Future<Output = Result<(), NotifyError>> + Send + 'a>>;
+
+pub trait Notifier: Send + Sync {
+    /// Short name used in logs
+    fn name(&self) -> &'static str;
+
+    fn notify<'a>(&'a self, message: &'a NewMessage) -> NotifyFuture<'a>;
+
+    /// Check that the destination is reachable, for the readiness probe
+    fn check(&self) -> NotifyFuture<'_> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+#[derive(Debug)]
+pub enum NotifyError {
+    Config(String),
+    Io(std::io::Error),
+    Email(lettre::error::Error),
+    Smtp(lettre::transport::smtp::Error),
+    Webhook(reqwest::Error),
+    Unreachable(String),
+    Timeout,
+}
+
+impl fmt::Display for NotifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotifyError::Config(msg) => write!(f, "invalid configuration: {}", msg),
+            NotifyError::Io(err) => write!(f, "write failed: {}", err),
+            NotifyError::Email(err) => write!(f, "could not build email: {}", err),
+            NotifyError::Smtp(err) => write!(f, "SMTP delivery failed: {}", err),
+            NotifyError::Webhook(err) => write!(f, "webhook delivery failed: {}", err),
+            NotifyError::Unreachable(msg) => write!(f, "unreachable: {}", msg),
+            NotifyError::Timeout => write!(f, "timed out"),
+        }
+    }
+}
+
+impl std::error::Error for NotifyError {}
+
+// Upper bound for a single delivery, so a stuck server can't pile up tasks
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(30);
+// Readiness checks must answer well within the probe timeout
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The notifiers configured at startup, invoked in the background after each submission
+#[derive(Clone, Default)]
+pub struct Notifiers {
+    notifiers: Vec<Arc<dyn Notifier>>,
+}
+
+impl Notifiers {
+    /// Build the notifiers from the configuration:
+    /// - `smtp_host` (plus the other `smtp_*` settings and `notify_email`) for email
+    /// - `notify_webhook_url` for an additional JSON webhook
+    /// - `notify_log_file` to append messages to a file
+    ///
+    /// Without SMTP or a log file, messages are printed to stdout.
+    pub fn from_config(config: &NotifyConfig) -> Result<Self, NotifyError> {
+        let mut notifiers: Vec<Arc<dyn Notifier>> = Vec::new();
+
+        if let Some(smtp) = SmtpNotifier::from_config(config)? {
+            notifiers.push(Arc::new(smtp));
+        }
+        if let Some(path) = &config.notify_log_file {
+            notifiers.push(Arc::new(LogNotifier::file(path)));
+        }
+        if notifiers.is_empty() {
+            notifiers.push(Arc::new(LogNotifier::stdout()));
+        }
+        if let Some(webhook) = WebhookNotifier::from_config(config)? {
+            notifiers.push(Arc::new(webhook));
+        }
+
+        Ok(Notifiers { notifiers })
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.notifiers.iter().map(|n| n.name()).collect()
+    }
+
+    /// Check every notifier concurrently, returning each one's name and result
+    pub async fn check(&self) -> Vec<(&'static str, Result<(), NotifyError>)> {
+        let checks: Vec<_> = self
+            .notifiers
+            .iter()
+            .map(|notifier| {
+                let notifier = notifier.clone();
+                tokio::spawn(async move {
+                    tokio::time::timeout(CHECK_TIMEOUT, notifier.check())
+                        .await
+                        .unwrap_or(Err(NotifyError::Timeout))
+                })
+            })
+            .collect();
+
+        let mut results = Vec::with_capacity(checks.len());
+        for (notifier, check) in self.notifiers.iter().zip(checks) {
+            let result = check
+                .await
+                .unwrap_or_else(|err| Err(NotifyError::Unreachable(err.to_string())));
+            results.push((notifier.name(), result));
+        }
+        results
+    }
+
+    /// Deliver the message without blocking the caller; failures are logged
+    pub fn dispatch(&self, message: NewMessage) {
+        for notifier in &self.notifiers {
+            let notifier = notifier.clone();
+            let message = message.clone();
+            tokio::spawn(async move {
+                let result = tokio::time::timeout(DELIVERY_TIMEOUT, notifier.notify(&message))
+                    .await
+                    .unwrap_or(Err(NotifyError::Timeout));
+                if let Err(err) = result {
+                    tracing::error!(notifier = notifier.name(), error = %err, "Notification failed");
+                }
+            });
+        }
+    }
+}
+
+// Plain text summary shared by the email and log notifiers
+fn format_message(message: &NewMessage) -> String {
+    format!(
+        "Nuevo mensaje de contacto\n\nNombre: {}\nEmail: {}\nIP: {}\nNavegador: {}\n\n{}\n",
+        message.name,
+        message.email,
+        message.ip.as_deref().unwrap_or("-"),
+        message.user_agent.as_deref().unwrap_or("-"),
+        message.message,
+    )
+}
@@ -0,0 +1,215 @@
+use super::{Notifier, NotifyError, NotifyFuture, format_message};
+use crate::config::{NotifyConfig, SmtpTls};
+use crate::messages::NewMessage;
+use lettre::{
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+    message::{Mailbox, header::ContentType},
+    transport::smtp::authentication::Credentials,
+};
+use std::time::Duration;
+
+/// Sends each message by email through an SMTP relay
+pub struct SmtpNotifier {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Mailbox,
+}
+
+impl SmtpNotifier {
+    /// Configured from the `smtp_*` settings and `notify_email`; None without `smtp_host`
+    pub fn from_config(config: &NotifyConfig) -> Result<Option<Self>, NotifyError> {
+        let Some(host) = &config.smtp_host else {
+            return Ok(None);
+        };
+
+        let to = mailbox("notify_email", config.notify_email.as_deref())?
+            .ok_or_else(|| NotifyError::Config("notify_email is required when smtp_host is set".into()))?;
+        let from = mailbox("smtp_from", config.smtp_from.as_deref())?.unwrap_or_else(|| to.clone());
+
+        let mut builder = match config.smtp_tls {
+            SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+            SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host).map_err(NotifyError::Smtp)?,
+            SmtpTls::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host).map_err(NotifyError::Smtp)?,
+        };
+
+        if let Some(port) = config.smtp_port {
+            builder = builder.port(port);
+        }
+        if let (Some(user), Some(password)) = (&config.smtp_user, &config.smtp_password) {
+            builder = builder.credentials(Credentials::new(user.clone(), password.expose().to_string()));
+        }
+
+        Ok(Some(SmtpNotifier {
+            transport: builder.timeout(Some(Duration::from_secs(10))).build(),
+            from,
+            to,
+        }))
+    }
+
+    fn build_email(&self, message: &NewMessage) -> Result<Message, NotifyError> {
+        let mut email = Message::builder()
+            .from(self.from.clone())
+            .to(self.to.clone())
+            .subject(format!("Nuevo mensaje de {}", message.name));
+
+        // Answer the visitor directly, when the address they typed parses
+        if let Ok(reply_to) = format!("{} <{}>", message.name, message.email).parse::<Mailbox>() {
+            email = email.reply_to(reply_to);
+        }
+
+        email
+            .header(ContentType::TEXT_PLAIN)
+            .body(format_message(message))
+            .map_err(NotifyError::Email)
+    }
+}
+
+impl Notifier for SmtpNotifier {
+    fn name(&self) -> &'static str {
+        "smtp"
+    }
+
+    fn notify<'a>(&'a self, message: &'a NewMessage) -> NotifyFuture<'a> {
+        Box::pin(async move {
+            let email = self.build_email(message)?;
+            self.transport.send(email).await.map_err(NotifyError::Smtp)?;
+            Ok(())
+        })
+    }
+
+    fn check(&self) -> NotifyFuture<'_> {
+        Box::pin(async move {
+            match self.transport.test_connection().await {
+                Ok(true) => Ok(()),
+                Ok(false) => Err(NotifyError::Unreachable("SMTP server refused the connection".to_string())),
+                Err(err) => Err(NotifyError::Smtp(err)),
+            }
+        })
+    }
+}
+
+fn mailbox(setting: &str, value: Option<&str>) -> Result<Option<Mailbox>, NotifyError> {
+    value
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|err| NotifyError::Config(format!("invalid {} {}: {}", setting, value, err)))
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+        task::JoinHandle,
+    };
+
+    // Answers one SMTP session on a local port, returning everything the client sent.
+    // Recipients are answered with `rcpt_reply`.
+    async fn mock_server(rcpt_reply: &'static str) -> (u16, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let session = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (reader, mut writer) = stream.into_split();
+            let mut lines = BufReader::new(reader).lines();
+            let mut transcript = String::new();
+            writer.write_all(b"220 localhost ESMTP mock\r\n").await.unwrap();
+
+            while let Ok(Some(line)) = lines.next_line().await {
+                transcript.push_str(&line);
+                transcript.push('\n');
+                let command = line.get(..4).unwrap_or_default().to_ascii_uppercase();
+                let reply = match command.as_str() {
+                    "EHLO" | "HELO" => "250 localhost\r\n",
+                    "RCPT" => rcpt_reply,
+                    "DATA" => {
+                        writer.write_all(b"354 End data with <CR><LF>.<CR><LF>\r\n").await.unwrap();
+                        while let Ok(Some(line)) = lines.next_line().await {
+                            if line == "." {
+                                break;
+                            }
+                            transcript.push_str(&line);
+                            transcript.push('\n');
+                        }
+                        "250 Queued\r\n"
+                    }
+                    "QUIT" => {
+                        writer.write_all(b"221 Bye\r\n").await.unwrap();
+                        break;
+                    }
+                    _ => "250 OK\r\n",
+                };
+                writer.write_all(reply.as_bytes()).await.unwrap();
+            }
+            transcript
+        });
+        (port, session)
+    }
+
+    fn notifier(port: u16) -> SmtpNotifier {
+        SmtpNotifier {
+            transport: AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("127.0.0.1")
+                .port(port)
+                .timeout(Some(Duration::from_secs(5)))
+                .build(),
+            from: "Web <web@example.com>".parse().unwrap(),
+            to: "Owner <owner@example.com>".parse().unwrap(),
+        }
+    }
+
+    fn message() -> NewMessage {
+        NewMessage {
+            name: "Ada".to_string(),
+            email: "ada@example.com".to_string(),
+            message: "Hola, me interesa tu proyecto".to_string(),
+            ip: Some("203.0.113.7".to_string()),
+            user_agent: None,
+        }
+    }
+
+    async fn transcript(session: JoinHandle<String>) -> String {
+        tokio::time::timeout(Duration::from_secs(5), session).await.unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn delivers_message_to_relay() {
+        let (port, session) = mock_server("250 OK\r\n").await;
+        notifier(port).notify(&message()).await.unwrap();
+
+        let transcript = transcript(session).await;
+        assert!(transcript.contains("MAIL FROM:<web@example.com>"));
+        assert!(transcript.contains("RCPT TO:<owner@example.com>"));
+        assert!(transcript.contains("Subject: Nuevo mensaje de Ada"));
+        assert!(transcript.contains("Reply-To: Ada <ada@example.com>"));
+        assert!(transcript.contains("Hola, me interesa tu proyecto"));
+        assert!(transcript.contains("IP: 203.0.113.7"));
+    }
+
+    #[tokio::test]
+    async fn rejected_recipient_is_an_error() {
+        let (port, session) = mock_server("550 No such user\r\n").await;
+        let result = notifier(port).notify(&message()).await;
+
+        assert!(matches!(result, Err(NotifyError::Smtp(_))));
+        assert!(!transcript(session).await.contains("DATA"));
+    }
+
+    #[tokio::test]
+    async fn check_connects_to_relay() {
+        let (port, session) = mock_server("250 OK\r\n").await;
+        notifier(port).check().await.unwrap();
+
+        assert!(transcript(session).await.starts_with("EHLO"));
+    }
+
+    #[tokio::test]
+    async fn check_fails_without_relay() {
+        // Bound and dropped, so nothing listens on the port
+        let port = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port();
+        assert!(notifier(port).check().await.is_err());
+    }
+}
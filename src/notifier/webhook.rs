@@ -0,0 +1,58 @@
+use super::{Notifier, NotifyError, NotifyFuture};
+use crate::config::NotifyConfig;
+use crate::messages::NewMessage;
+use std::time::Duration;
+
+/// Posts each message as JSON to a webhook (Slack/Discord relays, automation tools, ...)
+pub struct WebhookNotifier {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl WebhookNotifier {
+    /// Configured from `notify_webhook_url`
+    pub fn from_config(config: &NotifyConfig) -> Result<Option<Self>, NotifyError> {
+        let Some(url) = &config.notify_webhook_url else {
+            return Ok(None);
+        };
+        let url = url.expose().to_string();
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .map_err(NotifyError::Webhook)?;
+
+        Ok(Some(WebhookNotifier { client, url }))
+    }
+}
+
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    fn notify<'a>(&'a self, message: &'a NewMessage) -> NotifyFuture<'a> {
+        Box::pin(async move {
+            self.client
+                .post(&self.url)
+                .json(message)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .map_err(NotifyError::Webhook)?;
+            Ok(())
+        })
+    }
+
+    // Any HTTP response will do, many webhooks only accept POST
+    fn check(&self) -> NotifyFuture<'_> {
+        Box::pin(async move {
+            self.client
+                .head(&self.url)
+                .send()
+                .await
+                .map_err(NotifyError::Webhook)?;
+            Ok(())
+        })
+    }
+}
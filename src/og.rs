@@ -0,0 +1,351 @@
+use crate::content::Content;
+use crate::i18n::{Lang, Locale};
+use crate::routes::Route;
+use crate::state::AppState;
+use ab_glyph::{Font, FontRef, PxScale, ScaleFont, point};
+use axum::{
+    extract::{Path, State},
+    http::{
+        StatusCode,
+        header::{CACHE_CONTROL, CONTENT_TYPE},
+    },
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+use std::{fmt, fs, io, path::PathBuf, sync::LazyLock};
+use tiny_skia::{Color, GradientStop, LinearGradient, Paint, Pixmap, Point, PremultipliedColorU8, Rect, SpreadMode, Transform};
+
+// Open Graph images: a 1200x630 card with the page title and description drawn in the
+// colors of the electric-eclipse dark theme. `/og/pages/about.png` is the card of a section
+// (the `Route` name), `/og/<slug>.png` the card of a project or a blog post; the separate
+// prefix keeps a slug such as `about` from being taken for a section. Rendered PNGs are
+// kept in `<cache dir>/og`, named after a hash of their text.
+
+const WIDTH: u32 = 1200;
+const HEIGHT: u32 = 630;
+const MARGIN: f32 = 80.0;
+
+/// Bump when the drawing changes, so cached images are rendered again
+const DESIGN_VERSION: &str = "1";
+
+static REGULAR: LazyLock<FontRef<'static>> = LazyLock::new(|| {
+    FontRef::try_from_slice(include_bytes!("../assets/fonts/DejaVuSans.ttf")).expect("bundled font is valid")
+});
+static BOLD: LazyLock<FontRef<'static>> = LazyLock::new(|| {
+    FontRef::try_from_slice(include_bytes!("../assets/fonts/DejaVuSans-Bold.ttf")).expect("bundled font is valid")
+});
+
+// electric-eclipse/dark.css
+const BG_PRIMARY: [u8; 3] = [0x0f, 0x17, 0x2a];
+const BG_SECONDARY: [u8; 3] = [0x1e, 0x29, 0x3b];
+const ACCENT: [u8; 3] = [0x81, 0x8c, 0xf8];
+// --color-brand of light.css, for the side stripe
+const BRAND: [u8; 3] = [0x6d, 0x28, 0xd9];
+const TEXT_PRIMARY: [u8; 3] = [0xf1, 0xf5, 0xf9];
+const TEXT_SECONDARY: [u8; 3] = [0xcb, 0xd5, 0xe1];
+const TEXT_TERTIARY: [u8; 3] = [0x94, 0xa3, 0xb8];
+
+/// Path of the image for a project or post slug, without the language prefix
+pub fn image_path(slug: &str) -> String {
+    format!("/og/{}.png", slug)
+}
+
+/// Path of the image for a section, without the language prefix
+pub fn page_image_path(route: &Route) -> String {
+    format!("/og/pages/{}.png", route.name())
+}
+
+/// Text drawn on a card
+pub struct Card {
+    /// Shown above the title, e.g. the site name
+    pub eyebrow: String,
+    pub title: String,
+    pub description: String,
+    /// Shown at the bottom, e.g. the site address
+    pub footer: String,
+}
+
+#[derive(Debug)]
+pub enum OgError {
+    Canvas,
+    Encode(String),
+}
+
+impl fmt::Display for OgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OgError::Canvas => write!(f, "cannot allocate the image"),
+            OgError::Encode(err) => write!(f, "cannot encode PNG: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for OgError {}
+
+/// `GET /og/{slug}.png`
+pub async fn serve(State(state): State<AppState>, lang: Lang, Path(file): Path<String>) -> Response {
+    let site = state.content.current(lang.locale);
+    let card = file.strip_suffix(".png").and_then(|slug| slug_card(&site, slug));
+    respond(state, lang, card, &file).await
+}
+
+/// `GET /og/pages/{name}.png`
+pub async fn serve_page(State(state): State<AppState>, lang: Lang, Path(file): Path<String>) -> Response {
+    let site = state.content.current(lang.locale);
+    let card = file
+        .strip_suffix(".png")
+        .and_then(|name| page_card(&site, lang.locale, name));
+    respond(state, lang, card, &file).await
+}
+
+// The PNG of a card, or the 404 page when there is none
+async fn respond(state: AppState, lang: Lang, card: Option<Card>, file: &str) -> Response {
+    let Some(card) = card else {
+        return crate::not_found(State(state), lang).await.into_response();
+    };
+    let card = Card {
+        eyebrow: state.config.site_name.clone(),
+        footer: host(&state.config.base_url).to_string(),
+        ..card
+    };
+
+    // Drawing takes a few milliseconds of CPU, keep it off the async workers
+    let dir = state.config.cache_dir.join("og");
+    match tokio::task::spawn_blocking(move || cached_png(dir, &card)).await {
+        Ok(Ok(png)) => (
+            [(CONTENT_TYPE, "image/png"), (CACHE_CONTROL, "public, max-age=86400")],
+            png,
+        )
+            .into_response(),
+        Ok(Err(err)) => {
+            tracing::error!(error = %err, "Failed to render Open Graph image {}", file);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+        Err(err) => {
+            tracing::error!(error = %err, "Open Graph image task failed");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+fn text_card(title: &str, description: &str) -> Card {
+    Card {
+        eyebrow: String::new(),
+        title: title.to_string(),
+        description: description.to_string(),
+        footer: String::new(),
+    }
+}
+
+// Title and description of a section, by `Route` name
+fn page_card(site: &Content, locale: Locale, name: &str) -> Option<Card> {
+    let profile = &site.profile;
+    match Route::ALL.into_iter().find(|route| route.name() == name)? {
+        Route::Home => Some(text_card(&profile.name, &profile.title)),
+        Route::About => Some(text_card(locale.t("about.title"), &profile.summary)),
+        Route::Experience => Some(text_card(locale.t("experience.title"), locale.t("experience.description"))),
+        Route::Projects => Some(text_card(locale.t("projects.title"), locale.t("projects.description"))),
+        Route::Blog => Some(text_card(locale.t("blog.title"), locale.t("blog.intro"))),
+        Route::Contact => Some(text_card(locale.t("contact.title"), &profile.pitch)),
+        Route::Search
+        | Route::AdminMessages
+        | Route::ProjectDetail(_)
+        | Route::ProjectDemo(_)
+        | Route::ProjectCode(_) => None,
+    }
+}
+
+// Title and description of a project or published post
+fn slug_card(site: &Content, slug: &str) -> Option<Card> {
+    if let Some(project) = site.portfolio.project(slug) {
+        return Some(text_card(&project.title, &project.description));
+    }
+    let post = site.blog.post(slug).filter(|post| !post.draft)?;
+    Some(text_card(&post.title, &post.summary))
+}
+
+// `https://example.com:8080` -> `example.com:8080`
+fn host(base_url: &str) -> &str {
+    base_url.split_once("://").map_or(base_url, |(_, host)| host)
+}
+
+// The cache is best effort: a failed write is logged and the image served anyway
+fn cached_png(dir: PathBuf, card: &Card) -> Result<Vec<u8>, OgError> {
+    let mut hasher = Sha256::new();
+    for part in [DESIGN_VERSION, &card.eyebrow, &card.title, &card.description, &card.footer] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    let hash = hasher.finalize();
+    let name: String = hash[..12].iter().map(|byte| format!("{:02x}", byte)).collect();
+    let path = dir.join(format!("{}.png", name));
+
+    if let Ok(png) = fs::read(&path) {
+        return Ok(png);
+    }
+    let png = render(card)?;
+    let write = || -> io::Result<()> {
+        fs::create_dir_all(&dir)?;
+        // Written aside and renamed, so a concurrent request never reads half a file
+        let partial = path.with_extension("png.tmp");
+        fs::write(&partial, &png)?;
+        fs::rename(&partial, &path)
+    };
+    if let Err(err) = write() {
+        tracing::warn!(error = %err, "Cannot cache Open Graph image in {}", dir.display());
+    }
+    Ok(png)
+}
+
+pub fn render(card: &Card) -> Result<Vec<u8>, OgError> {
+    let mut pixmap = Pixmap::new(WIDTH, HEIGHT).ok_or(OgError::Canvas)?;
+    let (width, height) = (WIDTH as f32, HEIGHT as f32);
+
+    fill_gradient(&mut pixmap, (0.0, 0.0, width, height), BG_PRIMARY, BG_SECONDARY);
+    fill_gradient(&mut pixmap, (0.0, 0.0, 16.0, height), BRAND, ACCENT);
+
+    let text_width = width - 2.0 * MARGIN;
+    draw_text(&mut pixmap, &BOLD, 34.0, MARGIN, 130.0, &card.eyebrow, ACCENT);
+
+    // Long titles get a smaller size before they are cut
+    let (title_size, title_lines) = [72.0, 60.0, 50.0]
+        .into_iter()
+        .map(|size| (size, wrap(&BOLD, size, &card.title, text_width, 3)))
+        .find(|(_, lines)| !lines.last().is_some_and(|line| line.ends_with('…')))
+        .unwrap_or_else(|| (50.0, wrap(&BOLD, 50.0, &card.title, text_width, 3)));
+    let mut baseline = 130.0 + title_size * 1.5;
+    for line in &title_lines {
+        draw_text(&mut pixmap, &BOLD, title_size, MARGIN, baseline, line, TEXT_PRIMARY);
+        baseline += title_size * 1.2;
+    }
+
+    let description_size = 32.0;
+    baseline += description_size * 0.6;
+    // Lines whose baseline stays above the footer rule
+    let remaining_lines = if baseline > height - 140.0 {
+        0
+    } else {
+        ((height - 140.0 - baseline) / (description_size * 1.4)).floor() as usize + 1
+    };
+    for line in wrap(&REGULAR, description_size, &card.description, text_width, remaining_lines.min(3)) {
+        draw_text(&mut pixmap, &REGULAR, description_size, MARGIN, baseline, &line, TEXT_SECONDARY);
+        baseline += description_size * 1.4;
+    }
+
+    fill_gradient(&mut pixmap, (MARGIN, height - 110.0, 120.0, 4.0), ACCENT, BRAND);
+    draw_text(&mut pixmap, &REGULAR, 28.0, MARGIN, height - 60.0, &card.footer, TEXT_TERTIARY);
+
+    pixmap.encode_png().map_err(|err| OgError::Encode(err.to_string()))
+}
+
+// Diagonal gradient over the rectangle (x, y, width, height)
+fn fill_gradient(pixmap: &mut Pixmap, (x, y, width, height): (f32, f32, f32, f32), from: [u8; 3], to: [u8; 3]) {
+    let color = |[r, g, b]: [u8; 3]| Color::from_rgba8(r, g, b, 255);
+    let Some(rect) = Rect::from_xywh(x, y, width, height) else {
+        return;
+    };
+    let Some(shader) = LinearGradient::new(
+        Point::from_xy(x, y),
+        Point::from_xy(x + width, y + height),
+        vec![GradientStop::new(0.0, color(from)), GradientStop::new(1.0, color(to))],
+        SpreadMode::Pad,
+        Transform::identity(),
+    ) else {
+        return;
+    };
+    let paint = Paint {
+        shader,
+        anti_alias: true,
+        ..Paint::default()
+    };
+    pixmap.fill_rect(rect, &paint, Transform::identity(), None);
+}
+
+fn text_width(font: &FontRef, size: f32, text: &str) -> f32 {
+    let font = font.as_scaled(PxScale::from(size));
+    let mut previous = None;
+    let mut width = 0.0;
+    for c in text.chars() {
+        let id = font.glyph_id(c);
+        if let Some(previous) = previous {
+            width += font.kern(previous, id);
+        }
+        width += font.h_advance(id);
+        previous = Some(id);
+    }
+    width
+}
+
+// Greedy word wrap; the last line ends with an ellipsis when the text doesn't fit
+fn wrap(font: &FontRef, size: f32, text: &str, max_width: f32, max_lines: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut words = text.split_whitespace().peekable();
+    while let Some(word) = words.next() {
+        if lines.len() == max_lines {
+            break;
+        }
+        let mut line = word.to_string();
+        while let Some(next) = words.peek() {
+            let candidate = format!("{} {}", line, next);
+            if text_width(font, size, &candidate) > max_width {
+                break;
+            }
+            line = candidate;
+            words.next();
+        }
+        lines.push(line);
+    }
+
+    if words.peek().is_some()
+        && let Some(last) = lines.last_mut()
+    {
+        while !last.is_empty() && text_width(font, size, &format!("{}…", last)) > max_width {
+            last.pop();
+        }
+        *last = format!("{}…", last.trim_end());
+    }
+    lines
+}
+
+// Glyph coverage blended over the (opaque) background
+fn draw_text(pixmap: &mut Pixmap, font: &FontRef, size: f32, x: f32, baseline: f32, text: &str, [r, g, b]: [u8; 3]) {
+    let scale = PxScale::from(size);
+    let scaled = font.as_scaled(scale);
+    let (width, height) = (pixmap.width() as i32, pixmap.height() as i32);
+    let stride = pixmap.width() as usize;
+    let pixels = pixmap.pixels_mut();
+
+    let mut caret = x;
+    let mut previous = None;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(previous) = previous {
+            caret += scaled.kern(previous, id);
+        }
+        let glyph = id.with_scale_and_position(scale, point(caret, baseline));
+        caret += scaled.h_advance(id);
+        previous = Some(id);
+
+        let Some(outline) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outline.px_bounds();
+        outline.draw(|gx, gy, coverage| {
+            let px = bounds.min.x as i32 + gx as i32;
+            let py = bounds.min.y as i32 + gy as i32;
+            if px < 0 || py < 0 || px >= width || py >= height {
+                return;
+            }
+            let pixel = &mut pixels[py as usize * stride + px as usize];
+            let blend = |src: u8, dst: u8| (src as f32 * coverage + dst as f32 * (1.0 - coverage)).round() as u8;
+            if let Some(color) = PremultipliedColorU8::from_rgba(
+                blend(r, pixel.red()),
+                blend(g, pixel.green()),
+                blend(b, pixel.blue()),
+                255,
+            ) {
+                *pixel = color;
+            }
+        });
+    }
+}
@@ -0,0 +1,246 @@
+use crate::config::IpRange;
+use crate::i18n::{Lang, Locale};
+use crate::state::AppState;
+use crate::views;
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderMap, HeaderName, StatusCode, header::{FORWARDED, RETRY_AFTER}},
+    middleware::Next,
+    response::{Html, IntoResponse, Response},
+};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+// Per-IP token bucket, applied as a middleware layer to the routes it protects. Behind a
+// reverse proxy the client address comes from the headers the trusted proxies add.
+
+/// Set by most proxies; `Forwarded` (RFC 7239) is read when it is missing
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+pub struct RateLimiter {
+    capacity: f64,
+    /// Tokens regained per second
+    refill_rate: f64,
+    buckets: Mutex<HashMap<IpAddr, Bucket>>,
+}
+
+impl RateLimiter {
+    /// Allow bursts of `capacity` requests, then one more every `refill_every`
+    pub fn new(capacity: u32, refill_every: Duration) -> Self {
+        RateLimiter {
+            capacity: f64::from(capacity),
+            refill_rate: 1.0 / refill_every.as_secs_f64(),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Take a token for this client, or return how long until one is available
+    pub fn check(&self, ip: IpAddr) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        // Forget clients whose bucket has refilled completely, they are back to the default
+        if buckets.len() > 10_000 {
+            let full_after = self.capacity / self.refill_rate;
+            buckets.retain(|_, bucket| now.duration_since(bucket.updated).as_secs_f64() < full_after);
+        }
+
+        let bucket = buckets.entry(ip).or_insert(Bucket {
+            tokens: self.capacity,
+            updated: now,
+        });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.refill_rate).min(self.capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / self.refill_rate))
+        }
+    }
+}
+
+pub async fn limit(
+    State((state, limiter)): State<(AppState, Arc<RateLimiter>)>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match limiter.check(client_ip(addr.ip(), request.headers(), &state.config.trusted_proxies)) {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => {
+            let is_htmx = request.headers().contains_key("hx-request");
+            let locale = request.extensions().get::<Lang>().map_or(Locale::DEFAULT, |lang| lang.locale);
+            let retry_after = retry_after.as_secs().max(1).to_string();
+
+            if is_htmx {
+                let content = views::error_view::too_many_requests_content(locale);
+                (
+                    [(RETRY_AFTER, retry_after)],
+                    views::error_view::htmx_error(StatusCode::TOO_MANY_REQUESTS, content),
+                )
+                    .into_response()
+            } else {
+                (
+                    StatusCode::TOO_MANY_REQUESTS,
+                    [(RETRY_AFTER, retry_after)],
+                    Html(views::error_view::too_many_requests(&state.config, locale).into_string()),
+                )
+                    .into_response()
+            }
+        }
+    }
+}
+
+/// Address of the client that sent a request received from `peer`. Only trusted proxies
+/// can name someone else: the forwarding headers are read from the right, skipping
+/// trusted hops, and the first other address is the client.
+pub fn client_ip(peer: IpAddr, headers: &HeaderMap, trusted: &[IpRange]) -> IpAddr {
+    let is_trusted = |ip: IpAddr| trusted.iter().any(|range| range.contains(ip));
+    if !is_trusted(peer) {
+        return peer;
+    }
+
+    let hops = forwarded_for(headers);
+    let mut client = peer;
+    for hop in hops.iter().rev() {
+        // An entry that isn't an address (`unknown`, an obfuscated name) ends the chain
+        let Some(ip) = hop else {
+            break;
+        };
+        client = *ip;
+        if !is_trusted(client) {
+            break;
+        }
+    }
+    client
+}
+
+// Addresses in X-Forwarded-For, or the `for=` parameters of Forwarded, oldest first
+fn forwarded_for(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+    let values = |name| {
+        headers
+            .get_all(name)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .collect::<Vec<_>>()
+    };
+
+    let x_forwarded_for = values(HeaderName::from_static(X_FORWARDED_FOR));
+    if !x_forwarded_for.is_empty() {
+        return x_forwarded_for.into_iter().map(parse_node).collect();
+    }
+    values(FORWARDED)
+        .into_iter()
+        .filter_map(|element| {
+            element.split(';').find_map(|pair| {
+                let (name, value) = pair.split_once('=')?;
+                name.trim().eq_ignore_ascii_case("for").then(|| parse_node(value))
+            })
+        })
+        .collect()
+}
+
+// `192.0.2.1`, `192.0.2.1:4711`, `2001:db8::1` or `"[2001:db8::1]:4711"`
+fn parse_node(node: &str) -> Option<IpAddr> {
+    let node = node.trim().trim_matches('"');
+    if let Some(bracketed) = node.strip_prefix('[') {
+        return bracketed.split_once(']')?.0.parse().ok();
+    }
+    node.parse().ok().or_else(|| node.rsplit_once(':')?.0.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    fn ranges(values: &[&str]) -> Vec<IpRange> {
+        values.iter().map(|value| value.parse().unwrap()).collect()
+    }
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn untrusted_peer_is_the_client() {
+        let forwarded = headers(&[("x-forwarded-for", "198.51.100.7")]);
+        assert_eq!(client_ip(ip("203.0.113.9"), &forwarded, &ranges(&["10.0.0.0/8"])), ip("203.0.113.9"));
+        assert_eq!(client_ip(ip("10.0.0.2"), &forwarded, &[]), ip("10.0.0.2"));
+    }
+
+    #[test]
+    fn trusted_proxy_forwards_the_client() {
+        let forwarded = headers(&[("x-forwarded-for", "198.51.100.7")]);
+        assert_eq!(client_ip(ip("10.0.0.2"), &forwarded, &ranges(&["10.0.0.0/8"])), ip("198.51.100.7"));
+    }
+
+    #[test]
+    fn spoofed_entries_before_the_client_are_ignored() {
+        // The client sent its own X-Forwarded-For; the proxy appended the real address
+        let forwarded = headers(&[("x-forwarded-for", "1.2.3.4, 198.51.100.7, 10.0.0.3")]);
+        let trusted = ranges(&["10.0.0.0/8"]);
+        assert_eq!(client_ip(ip("10.0.0.2"), &forwarded, &trusted), ip("198.51.100.7"));
+    }
+
+    #[test]
+    fn repeated_headers_are_one_list() {
+        let forwarded = headers(&[("x-forwarded-for", "198.51.100.7"), ("x-forwarded-for", "10.0.0.3")]);
+        assert_eq!(client_ip(ip("10.0.0.2"), &forwarded, &ranges(&["10.0.0.0/8"])), ip("198.51.100.7"));
+    }
+
+    #[test]
+    fn forwarded_header_is_read_without_x_forwarded_for() {
+        let forwarded = headers(&[("forwarded", "for=\"[2001:db8::17]:4711\";proto=https, for=192.0.2.60:80")]);
+        let trusted = ranges(&["127.0.0.1", "192.0.2.60"]);
+        assert_eq!(client_ip(ip("127.0.0.1"), &forwarded, &trusted), ip("2001:db8::17"));
+    }
+
+    #[test]
+    fn unknown_hop_ends_the_chain() {
+        let forwarded = headers(&[("forwarded", "for=198.51.100.7, for=unknown")]);
+        assert_eq!(client_ip(ip("127.0.0.1"), &forwarded, &ranges(&["127.0.0.1"])), ip("127.0.0.1"));
+    }
+
+    #[test]
+    fn ranges_match_their_network() {
+        let network: IpRange = "172.16.0.0/12".parse().unwrap();
+        assert!(network.contains(ip("172.31.255.1")));
+        assert!(!network.contains(ip("172.32.0.1")));
+        assert!(network.contains(ip("::ffff:172.16.0.1")));
+        assert!("0.0.0.0/0".parse::<IpRange>().unwrap().contains(ip("8.8.8.8")));
+        assert!("10.0.0.0/33".parse::<IpRange>().is_err());
+        assert!("proxy".parse::<IpRange>().is_err());
+    }
+
+    #[test]
+    fn bucket_refuses_past_its_capacity() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(60));
+        assert!(limiter.check(ip("198.51.100.7")).is_ok());
+        assert!(limiter.check(ip("198.51.100.7")).is_ok());
+        assert!(limiter.check(ip("198.51.100.7")).is_err());
+        assert!(limiter.check(ip("198.51.100.8")).is_ok());
+    }
+}
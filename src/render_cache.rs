@@ -0,0 +1,313 @@
+use crate::csrf::CsrfToken;
+use crate::i18n::{Lang, Locale};
+use axum::{
+    extract::{FromRequestParts, Path, Query},
+    http::{HeaderName, HeaderValue, request::Parts},
+    response::{Html, IntoResponse, Response},
+};
+use axum_htmx::HxTarget;
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    collections::{HashMap, VecDeque},
+    convert::Infallible,
+    sync::{Arc, Mutex},
+};
+
+// Rendered pages of `generate_page_handler!`, kept in memory so a page is built once per
+// content version instead of on every request. Entries are keyed by path, the query
+// parameters the handler reads (parsed, so `?page=01` and `?page=1` are one entry), full
+// page or htmx fragment (and the htmx target), and language; requests with parameters the
+// handler ignores aren't cached, so made-up query strings can't push pages out. The whole
+// cache is dropped when the content version changes. Full pages are stored with a
+// placeholder for the visitor's CSRF token, filled in on the way out. Pages that differ
+// per visitor beyond that (the contact form and its single-use token) opt out with
+// `Page::uncached`.
+
+/// Tells whether the response came from the cache: `hit`, `miss` or `bypass`
+pub const STATUS_HEADER: &str = "x-render-cache";
+
+/// Bytes of HTML kept; the oldest entries are dropped first
+const MAX_BYTES: usize = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Key {
+    /// Without the language prefix
+    path: String,
+    /// Names of the query parameters sent, until `Key::read` replaces them with `query`
+    params: Vec<String>,
+    /// Query parameters read by the handler, as JSON
+    query: Vec<String>,
+    locale: Locale,
+    htmx: bool,
+    /// `HX-Target`, for views that render less for some targets
+    target: Option<String>,
+}
+
+impl Key {
+    /// The key for what the handler reads: `parts` are the query parameters each of its
+    /// extractors parsed (see [`KeyPart`]). None when the request has other parameters.
+    pub fn read(mut self, parts: &[Option<Value>]) -> Option<Key> {
+        let read: Vec<&Value> = parts.iter().flatten().collect();
+        if !self.params.iter().all(|name| read.iter().any(|query| query.get(name).is_some())) {
+            return None;
+        }
+        self.params.clear();
+        self.query = read.iter().map(|query| query.to_string()).collect();
+        Some(self)
+    }
+}
+
+/// The page a request asks for, from its path (after `i18n::negotiate`) and htmx headers
+impl<S: Send + Sync> FromRequestParts<S> for Key {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Ok(lang) = Lang::from_request_parts(parts, state).await;
+        let header = |name: &str| parts.headers.get(name).and_then(|v| v.to_str().ok());
+        let htmx = header("hx-request") == Some("true");
+        let mut params: Vec<String> = form_urlencoded::parse(parts.uri.query().unwrap_or_default().as_bytes())
+            .map(|(name, _)| name.into_owned())
+            .collect();
+        params.sort_unstable();
+        params.dedup();
+        Ok(Key {
+            path: parts.uri.path().to_string(),
+            params,
+            query: Vec::new(),
+            locale: lang.locale,
+            htmx,
+            target: header("hx-target").filter(|_| htmx).map(str::to_string),
+        })
+    }
+}
+
+/// Extractors of cached handlers, by the query parameters they read
+pub trait KeyPart {
+    /// Parameters read, with their parsed values; None when the query string isn't read
+    fn query(&self) -> Option<Value> {
+        None
+    }
+}
+
+impl<T: Serialize> KeyPart for Query<T> {
+    fn query(&self) -> Option<Value> {
+        // Every field, unset ones as null, so the names of the parameters are all there
+        serde_json::to_value(&self.0).ok()
+    }
+}
+
+impl<T> KeyPart for Path<T> {}
+
+impl KeyPart for HxTarget {}
+
+#[derive(Default)]
+struct Entries {
+    /// Content version the entries were rendered from
+    version: u64,
+    pages: HashMap<Key, Arc<str>>,
+    /// Insertion order, for eviction
+    order: VecDeque<Key>,
+    bytes: usize,
+}
+
+impl Entries {
+    // Whether `version` is the newest seen; pages rendered from older content are dropped
+    fn sync(&mut self, version: u64) -> bool {
+        if version > self.version {
+            self.pages.clear();
+            self.order.clear();
+            self.bytes = 0;
+            self.version = version;
+        }
+        version == self.version
+    }
+}
+
+pub struct RenderCache {
+    entries: Mutex<Entries>,
+    max_bytes: usize,
+}
+
+impl Default for RenderCache {
+    fn default() -> Self {
+        RenderCache {
+            entries: Mutex::default(),
+            max_bytes: MAX_BYTES,
+        }
+    }
+}
+
+impl RenderCache {
+    /// The cached page for `key`, rendered from content `version`, with the visitor's token
+    pub fn get(&self, key: &Key, version: u64, csrf: &CsrfToken) -> Option<Response> {
+        let html = {
+            let mut entries = self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            if !entries.sync(version) {
+                return None;
+            }
+            entries.pages.get(key)?.clone()
+        };
+        Some(respond(csrf.fill(&html), "hit"))
+    }
+
+    /// Respond with `html` and, with a `key`, keep it for the next requests for it; None
+    /// for pages that aren't cached. `version` must be read before the content the page
+    /// was rendered from.
+    pub fn store(&self, key: Option<Key>, version: u64, html: String, csrf: &CsrfToken) -> Response {
+        let Some(key) = key else {
+            return respond(html, "bypass");
+        };
+        let response = respond(csrf.fill(&html), "miss");
+        // Larger than the whole cache: not worth evicting everything else for
+        if html.len() > self.max_bytes {
+            return response;
+        }
+
+        let mut entries = self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        // A request that started before a reload rendered the old content
+        if !entries.sync(version) {
+            return response;
+        }
+        entries.bytes += html.len();
+        if let Some(previous) = entries.pages.insert(key.clone(), html.into()) {
+            // Rendered twice at the same time; same content, same place in the queue
+            entries.bytes -= previous.len();
+        } else {
+            entries.order.push_back(key);
+        }
+        while entries.bytes > self.max_bytes {
+            let Some(oldest) = entries.order.pop_front() else {
+                break;
+            };
+            if let Some(page) = entries.pages.remove(&oldest) {
+                entries.bytes -= page.len();
+            }
+        }
+        response
+    }
+}
+
+fn respond(html: String, status: &'static str) -> Response {
+    let mut response = Html(html).into_response();
+    response
+        .headers_mut()
+        .insert(HeaderName::from_static(STATUS_HEADER), HeaderValue::from_static(status));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::to_bytes, http::Request};
+
+    #[derive(Serialize)]
+    struct PageQuery {
+        page: Option<usize>,
+    }
+
+    async fn key(uri: &str) -> Key {
+        let (mut parts, ()) = Request::get(uri).body(()).unwrap().into_parts();
+        let Ok(key) = Key::from_request_parts(&mut parts, &()).await;
+        key
+    }
+
+    async fn page_key(uri: &str, page: Option<usize>) -> Option<Key> {
+        key(uri).await.read(&[Query(PageQuery { page }).query()])
+    }
+
+    fn cache(max_bytes: usize) -> RenderCache {
+        RenderCache {
+            entries: Mutex::default(),
+            max_bytes,
+        }
+    }
+
+    fn store(cache: &RenderCache, key: &Key, version: u64, html: &str) -> Response {
+        cache.store(Some(key.clone()), version, html.to_string(), &CsrfToken::placeholder())
+    }
+
+    fn cached(cache: &RenderCache, key: &Key, version: u64) -> bool {
+        cache.get(key, version, &CsrfToken::placeholder()).is_some()
+    }
+
+    fn status(response: &Response) -> &str {
+        response.headers()[STATUS_HEADER].to_str().unwrap()
+    }
+
+    #[tokio::test]
+    async fn key_keeps_the_parameters_the_handler_reads() {
+        let first = page_key("/blog?page=1", Some(1)).await.unwrap();
+        assert_eq!(page_key("/blog?page=01", Some(1)).await, Some(first.clone()));
+        assert_ne!(page_key("/blog?page=2", Some(2)).await, Some(first.clone()));
+        assert_ne!(page_key("/blog", None).await, Some(first));
+
+        // Made-up parameters would each get an entry
+        assert_eq!(page_key("/blog?page=1&a=1", Some(1)).await, None);
+        assert_eq!(key("/about?a=1").await.read(&[]), None);
+        assert!(key("/about").await.read(&[]).is_some());
+    }
+
+    #[tokio::test]
+    async fn stores_and_serves_pages() {
+        let cache = cache(MAX_BYTES);
+        let key = key("/about").await.read(&[]).unwrap();
+        assert!(!cached(&cache, &key, 1));
+
+        let response = store(&cache, &key, 1, "<p>about</p>");
+        assert_eq!(status(&response), "miss");
+        let response = cache.get(&key, 1, &CsrfToken::placeholder()).unwrap();
+        assert_eq!(status(&response), "hit");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body, "<p>about</p>");
+
+        let response = cache.store(None, 1, "<p>contact</p>".to_string(), &CsrfToken::placeholder());
+        assert_eq!(status(&response), "bypass");
+    }
+
+    #[tokio::test]
+    async fn evicts_oldest_pages_first() {
+        let cache = cache(10);
+        let [a, b, c] = [key("/a").await, key("/b").await, key("/c").await];
+        store(&cache, &a, 1, "aaaa");
+        store(&cache, &b, 1, "bbbb");
+        // Stored again: same bytes, same place in the queue
+        store(&cache, &a, 1, "aaaa");
+        store(&cache, &c, 1, "cccc");
+
+        assert!(!cached(&cache, &a, 1));
+        assert!(cached(&cache, &b, 1));
+        assert!(cached(&cache, &c, 1));
+    }
+
+    #[tokio::test]
+    async fn stays_within_byte_limit() {
+        let cache = cache(10);
+        let [small, large] = [key("/small").await, key("/large").await];
+        store(&cache, &small, 1, "small");
+        // Larger than the whole cache: sent, but not kept, and nothing is evicted for it
+        let response = store(&cache, &large, 1, "much too large");
+        assert_eq!(status(&response), "miss");
+        assert!(!cached(&cache, &large, 1));
+        assert!(cached(&cache, &small, 1));
+
+        let entries = cache.entries.lock().unwrap();
+        assert_eq!(entries.bytes, "small".len());
+    }
+
+    #[tokio::test]
+    async fn drops_pages_of_older_content() {
+        let cache = cache(MAX_BYTES);
+        let key = key("/about").await;
+        store(&cache, &key, 1, "old");
+        assert!(!cached(&cache, &key, 2));
+        // Rendered before the reload, finished after it
+        store(&cache, &key, 1, "old");
+        assert!(!cached(&cache, &key, 2));
+        assert!(!cached(&cache, &key, 1));
+
+        store(&cache, &key, 2, "new");
+        assert!(cached(&cache, &key, 2));
+        assert_eq!(cache.entries.lock().unwrap().bytes, "new".len());
+    }
+}
@@ -1,19 +1,86 @@
+use std::borrow::Cow;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Route {
     Home,
     About,
     Contact,
     Experience,
     Projects,
+    /// Detail page of the project with this slug
+    ProjectDetail(String),
+    /// Redirect to the live demo of the project with this slug
+    ProjectDemo(String),
+    /// Redirect to the repository of the project with this slug
+    ProjectCode(String),
+    Blog,
+    Search,
+    AdminMessages,
 }
 
 impl Route {
-    pub fn path(&self) -> &'static str {
+    /// Routes without parameters
+    pub const ALL: [Route; 8] = [
+        Route::Home,
+        Route::About,
+        Route::Contact,
+        Route::Experience,
+        Route::Projects,
+        Route::Blog,
+        Route::Search,
+        Route::AdminMessages,
+    ];
+
+    /// Path templates the router matches project pages with
+    pub const PROJECT_DETAIL_PATTERN: &'static str = "/projects/{slug}";
+    pub const PROJECT_DEMO_PATTERN: &'static str = "/projects/demo/{slug}";
+    pub const PROJECT_CODE_PATTERN: &'static str = "/projects/code/{slug}";
+
+    pub fn from_path(path: &str) -> Option<Route> {
+        Route::ALL.into_iter().find(|route| route.path() == path)
+    }
+
+    /// Route of a router path template (`MatchedPath`), routes with parameters included;
+    /// the parameters themselves are unknown, so they are left empty
+    pub fn from_pattern(pattern: &str) -> Option<Route> {
+        match pattern {
+            Route::PROJECT_DETAIL_PATTERN => Some(Route::ProjectDetail(String::new())),
+            Route::PROJECT_DEMO_PATTERN => Some(Route::ProjectDemo(String::new())),
+            Route::PROJECT_CODE_PATTERN => Some(Route::ProjectCode(String::new())),
+            _ => Route::from_path(pattern),
+        }
+    }
+
+    /// Stable identifier, used as a metrics label
+    pub fn name(&self) -> &'static str {
+        match self {
+            Route::Home => "home",
+            Route::About => "about",
+            Route::Contact => "contact",
+            Route::Experience => "experience",
+            Route::Projects => "projects",
+            Route::ProjectDetail(_) => "project_detail",
+            Route::ProjectDemo(_) => "project_demo",
+            Route::ProjectCode(_) => "project_code",
+            Route::Blog => "blog",
+            Route::Search => "search",
+            Route::AdminMessages => "admin_messages",
+        }
+    }
+
+    pub fn path(&self) -> Cow<'static, str> {
         match self {
-            Route::Home => "/",
-            Route::About => "/about",
-            Route::Contact => "/contact",
-            Route::Experience => "/experience",
-            Route::Projects => "/projects",
+            Route::Home => "/".into(),
+            Route::About => "/about".into(),
+            Route::Contact => "/contact".into(),
+            Route::Experience => "/experience".into(),
+            Route::Projects => "/projects".into(),
+            Route::ProjectDetail(slug) => format!("/projects/{}", slug).into(),
+            Route::ProjectDemo(slug) => format!("/projects/demo/{}", slug).into(),
+            Route::ProjectCode(slug) => format!("/projects/code/{}", slug).into(),
+            Route::Blog => "/blog".into(),
+            Route::Search => "/search".into(),
+            Route::AdminMessages => "/admin/messages".into(),
         }
     }
 }
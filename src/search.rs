@@ -0,0 +1,306 @@
+use crate::content::Content;
+use crate::i18n::Locale;
+use crate::routes::Route;
+use crate::views::blog_view;
+use rust_stemmers::{Algorithm, Stemmer};
+use std::collections::HashMap;
+
+// Full-text search over the pages, projects, experience entries and blog posts of one
+// language. The index is built with the content (see `Content::load`), so every reload
+// rebuilds it. Words are lowercased, stemmed with the Snowball stemmer of the language
+// and stripped of accents: "Proyectos", "proyecto" and "proyécto" find the same pages.
+
+/// Occurrences in a title count this many times more than in the text
+const TITLE_WEIGHT: f32 = 3.0;
+/// Words of text shown around the first match
+const SNIPPET_WORDS: usize = 24;
+/// Words before the first match
+const SNIPPET_CONTEXT: usize = 6;
+/// The last word of a query can be unfinished (as you type) from this many letters
+const MIN_PREFIX: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Page,
+    Project,
+    Experience,
+    Post,
+}
+
+#[derive(Debug, Clone)]
+pub struct Document {
+    pub kind: Kind,
+    pub title: String,
+    /// Without the language prefix
+    pub path: String,
+    /// Plain text the snippets are cut from
+    pub text: String,
+}
+
+/// Inverted index of one language: stem -> documents and weights
+#[derive(Debug, Clone)]
+pub struct SearchIndex {
+    locale: Locale,
+    documents: Vec<Document>,
+    postings: HashMap<String, Vec<(usize, f32)>>,
+}
+
+/// A matching document and its snippet: pieces of text, `true` for the matched words
+pub struct Hit<'a> {
+    pub document: &'a Document,
+    pub snippet: Vec<(&'a str, bool)>,
+}
+
+impl SearchIndex {
+    /// Finds nothing; stands in while the content it indexes is being loaded
+    pub fn empty(locale: Locale) -> Self {
+        SearchIndex {
+            locale,
+            documents: Vec::new(),
+            postings: HashMap::new(),
+        }
+    }
+
+    pub fn build(content: &Content, locale: Locale) -> Self {
+        let stemmer = stemmer(locale);
+        let documents = documents(content, locale);
+
+        let mut weights: HashMap<String, HashMap<usize, f32>> = HashMap::new();
+        for (id, document) in documents.iter().enumerate() {
+            for (text, weight) in [(&document.title, TITLE_WEIGHT), (&document.text, 1.0)] {
+                for (_, word) in words(text) {
+                    *weights.entry(normalize(&stemmer, word)).or_default().entry(id).or_default() += weight;
+                }
+            }
+        }
+        let postings = weights
+            .into_iter()
+            .map(|(stem, documents)| (stem, documents.into_iter().collect()))
+            .collect();
+
+        SearchIndex {
+            locale,
+            documents,
+            postings,
+        }
+    }
+
+    /// Documents containing every word of `query`, best first
+    pub fn search(&self, query: &str) -> Vec<Hit<'_>> {
+        let stemmer = stemmer(self.locale);
+        let mut terms: Vec<String> = words(query).map(|(_, word)| normalize(&stemmer, word)).collect();
+        terms.dedup();
+        let Some(last) = terms.len().checked_sub(1) else {
+            return Vec::new();
+        };
+
+        // Document -> (score, number of terms found)
+        let mut scores: HashMap<usize, (f32, usize)> = HashMap::new();
+        for (i, term) in terms.iter().enumerate() {
+            let prefix = i == last && term.chars().count() >= MIN_PREFIX;
+            let mut found: HashMap<usize, f32> = HashMap::new();
+            for (stem, postings) in &self.postings {
+                if stem != term && !(prefix && stem.starts_with(term.as_str())) {
+                    continue;
+                }
+                // Rare words weigh more than words found everywhere
+                let idf = (1.0 + self.documents.len() as f32 / postings.len() as f32).ln();
+                for &(id, weight) in postings {
+                    let score = found.entry(id).or_default();
+                    *score = score.max(weight * idf);
+                }
+            }
+            for (id, score) in found {
+                let entry = scores.entry(id).or_default();
+                entry.0 += score;
+                entry.1 += 1;
+            }
+        }
+
+        let mut ranked: Vec<(usize, f32)> = scores
+            .into_iter()
+            .filter(|(_, (_, found))| *found == terms.len())
+            .map(|(id, (score, _))| (id, score))
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        let matches = |word: &str| {
+            let stem = normalize(&stemmer, word);
+            terms
+                .iter()
+                .enumerate()
+                .any(|(i, term)| stem == *term || (i == last && term.chars().count() >= MIN_PREFIX && stem.starts_with(term.as_str())))
+        };
+        ranked
+            .into_iter()
+            .map(|(id, _)| {
+                let document = &self.documents[id];
+                Hit {
+                    document,
+                    snippet: snippet(&document.text, &matches),
+                }
+            })
+            .collect()
+    }
+}
+
+fn stemmer(locale: Locale) -> Stemmer {
+    Stemmer::create(match locale {
+        Locale::Es => Algorithm::Spanish,
+        Locale::En => Algorithm::English,
+    })
+}
+
+// Words and their byte offsets; anything that is not a letter or a digit separates words
+fn words(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(move |word| (word.as_ptr() as usize - text.as_ptr() as usize, word))
+}
+
+fn normalize(stemmer: &Stemmer, word: &str) -> String {
+    stemmer.stem(&word.to_lowercase()).chars().map(fold).collect()
+}
+
+// Accents are often left out when typing
+fn fold(c: char) -> char {
+    match c {
+        'á' | 'à' | 'ä' | 'â' => 'a',
+        'é' | 'è' | 'ë' | 'ê' => 'e',
+        'í' | 'ì' | 'ï' | 'î' => 'i',
+        'ó' | 'ò' | 'ö' | 'ô' => 'o',
+        'ú' | 'ù' | 'ü' | 'û' => 'u',
+        'ñ' => 'n',
+        'ç' => 'c',
+        _ => c,
+    }
+}
+
+// SNIPPET_WORDS words from a little before the first match, matched words marked
+fn snippet<'a>(text: &'a str, matches: &impl Fn(&str) -> bool) -> Vec<(&'a str, bool)> {
+    let words: Vec<(usize, &str)> = words(text).collect();
+    if words.is_empty() {
+        return Vec::new();
+    }
+    let first = words.iter().position(|(_, word)| matches(word)).unwrap_or(0);
+    let start = first.saturating_sub(SNIPPET_CONTEXT);
+    let end = (start + SNIPPET_WORDS).min(words.len());
+    let (last_offset, last_word) = words[end - 1];
+
+    let mut parts = Vec::new();
+    if start > 0 {
+        parts.push(("… ", false));
+    }
+    let mut cursor = words[start].0;
+    for &(offset, word) in &words[start..end] {
+        if matches(word) {
+            parts.push((&text[cursor..offset], false));
+            parts.push((word, true));
+            cursor = offset + word.len();
+        }
+    }
+    parts.push((&text[cursor..last_offset + last_word.len()], false));
+    if end < words.len() {
+        parts.push((" …", false));
+    }
+    parts
+}
+
+// Sections, projects, jobs, education and posts, with the text their pages show
+fn documents(content: &Content, locale: Locale) -> Vec<Document> {
+    let profile = &content.profile;
+    let experience = &content.experience;
+    let portfolio = &content.portfolio;
+    let mut documents = Vec::new();
+
+    for route in Route::ALL {
+        let (title, text): (&str, Vec<&str>) = match route {
+            Route::Home => (
+                &profile.name,
+                [&profile.title, &profile.summary].into_iter().chain(&profile.bio).map(String::as_str).collect(),
+            ),
+            Route::About => (
+                locale.t("about.title"),
+                [&profile.summary]
+                    .into_iter()
+                    .chain(&profile.bio)
+                    .chain(content.skills.iter().flat_map(|category| [&category.name].into_iter().chain(&category.skills)))
+                    .map(String::as_str)
+                    .collect(),
+            ),
+            Route::Experience => (
+                locale.t("experience.title"),
+                [locale.t("experience.description")]
+                    .into_iter()
+                    .chain(experience.education.iter().flat_map(|entry| [entry.title.as_str(), &entry.institution]))
+                    .collect(),
+            ),
+            Route::Projects => (
+                locale.t("projects.title"),
+                [locale.t("projects.description")]
+                    .into_iter()
+                    .chain(portfolio.open_source.iter().flat_map(|entry| [entry.name.as_str(), &entry.description]))
+                    .collect(),
+            ),
+            Route::Blog => (locale.t("blog.title"), vec![locale.t("blog.intro")]),
+            Route::Contact => (
+                locale.t("contact.title"),
+                [&profile.pitch]
+                    .into_iter()
+                    .chain(&profile.strengths)
+                    .map(String::as_str)
+                    .chain(profile.availability.iter().flat_map(|item| [item.title.as_str(), &item.description]))
+                    .collect(),
+            ),
+            // Indexed below, one document each
+            Route::ProjectDetail(_) => continue,
+            // Redirects to other sites
+            Route::ProjectDemo(_) | Route::ProjectCode(_) => continue,
+            Route::Search | Route::AdminMessages => continue,
+        };
+        documents.push(Document {
+            kind: Kind::Page,
+            title: title.to_string(),
+            path: route.path().into_owned(),
+            text: text.join(" "),
+        });
+    }
+
+    for project in &portfolio.projects {
+        let text: Vec<&str> = [&project.description]
+            .into_iter()
+            .chain(&project.details)
+            .chain(&project.role)
+            .chain(&project.outcomes)
+            .chain(&project.tags)
+            .map(String::as_str)
+            .collect();
+        documents.push(Document {
+            kind: Kind::Project,
+            title: project.title.clone(),
+            path: Route::ProjectDetail(project.slug.clone()).path().into_owned(),
+            text: text.join(" "),
+        });
+    }
+
+    for job in &experience.jobs {
+        let text: Vec<&str> = [&job.summary].into_iter().chain(&job.highlights).map(String::as_str).collect();
+        documents.push(Document {
+            kind: Kind::Experience,
+            title: format!("{} · {}", job.title, job.company),
+            path: Route::Experience.path().into_owned(),
+            text: text.join(" "),
+        });
+    }
+
+    for post in content.blog.posts().into_iter().filter(|post| !post.draft) {
+        documents.push(Document {
+            kind: Kind::Post,
+            title: post.title.clone(),
+            path: blog_view::post_path(&post.slug),
+            text: format!("{} {}", post.summary, post.text),
+        });
+    }
+
+    documents
+}
@@ -0,0 +1,216 @@
+use crate::config::Config;
+use crate::content::{Content, ContentStore};
+use crate::feed;
+use crate::files;
+use crate::i18n::Locale;
+use crate::routes::Route;
+use crate::views::blog_view;
+use chrono::{DateTime, NaiveDate, Utc};
+use std::{
+    fmt::Write,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+// sitemap.xml built from the `Route` variants and the content (projects, blog posts and tags), one
+// <url> per page and language with hreflang alternates. Rebuilt after each content reload.
+
+/// Limit of the sitemap protocol; past it /sitemap.xml becomes an index of /sitemaps/N.xml
+const MAX_URLS: usize = 50_000;
+
+struct Page {
+    /// Without the language prefix
+    path: String,
+    lastmod: Option<NaiveDate>,
+    changefreq: &'static str,
+    priority: &'static str,
+}
+
+/// Rendered documents: a single sitemap, or an index and its parts
+pub struct Sitemap {
+    pub index: Option<String>,
+    pub parts: Vec<String>,
+}
+
+impl Sitemap {
+    /// Document for /sitemap.xml
+    pub fn root(&self) -> &str {
+        self.index.as_deref().unwrap_or(&self.parts[0])
+    }
+
+    /// Document for /sitemaps/{number}.xml, numbered from 1
+    pub fn part(&self, number: usize) -> Option<&str> {
+        self.index.as_ref()?;
+        self.parts.get(number.checked_sub(1)?).map(String::as_str)
+    }
+}
+
+/// Last built sitemap and the content version it was built from
+#[derive(Default)]
+pub struct SitemapCache {
+    cached: Mutex<Option<(u64, Arc<Sitemap>)>>,
+}
+
+impl SitemapCache {
+    pub fn get(&self, config: &Config, content: &ContentStore) -> Arc<Sitemap> {
+        let version = content.version();
+        let mut cached = self.cached.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        match cached.as_ref() {
+            Some((built_from, sitemap)) if *built_from == version => sitemap.clone(),
+            _ => {
+                let sitemap = Arc::new(build(config, &content.current(Locale::DEFAULT)));
+                *cached = Some((version, sitemap.clone()));
+                sitemap
+            }
+        }
+    }
+}
+
+fn build(config: &Config, content: &Content) -> Sitemap {
+    let pages = pages(&config.data_dir, content);
+    // Each page is listed once per language
+    let per_part = MAX_URLS / Locale::ALL.len();
+    let parts: Vec<String> = pages.chunks(per_part).map(|pages| urlset(config, pages)).collect();
+
+    match parts.len() {
+        0 => Sitemap {
+            index: None,
+            parts: vec![urlset(config, &[])],
+        },
+        1 => Sitemap { index: None, parts },
+        count => Sitemap {
+            index: Some(sitemap_index(config, count)),
+            parts,
+        },
+    }
+}
+
+// Every public page; the content decides which posts and tags exist
+fn pages(data_dir: &Path, content: &Content) -> Vec<Page> {
+    let posts: Vec<_> = content.blog.posts().into_iter().filter(|post| !post.draft).collect();
+    let newest_post = posts.iter().map(|post| post.date).max();
+
+    let mut pages = Vec::new();
+    for route in Route::ALL {
+        let (files, changefreq, priority): (&[&str], _, _) = match route {
+            Route::Home => (&["profile.json"], "weekly", "1.0"),
+            Route::About => (&["profile.json", "skills.json"], "monthly", "0.8"),
+            Route::Experience => (&["experience.json"], "monthly", "0.8"),
+            Route::Projects => (&["projects.json"], "weekly", "0.9"),
+            Route::Blog => (&[], "weekly", "0.8"),
+            Route::Contact => (&["profile.json"], "monthly", "0.7"),
+            // Listed below, one per project
+            Route::ProjectDetail(_) => continue,
+            // Redirects to other sites
+            Route::ProjectDemo(_) | Route::ProjectCode(_) => continue,
+            // Results pages are noindex
+            Route::Search => continue,
+            Route::AdminMessages => continue,
+        };
+        let lastmod = files
+            .iter()
+            .filter_map(|file| modified(data_dir, file))
+            .chain(newest_post.filter(|_| route == Route::Blog))
+            .max();
+        pages.push(Page {
+            path: route.path().to_string(),
+            lastmod,
+            changefreq,
+            priority,
+        });
+    }
+
+    for project in &content.portfolio.projects {
+        pages.push(Page {
+            path: Route::ProjectDetail(project.slug.clone()).path().into_owned(),
+            lastmod: modified(data_dir, "projects.json"),
+            changefreq: "monthly",
+            priority: "0.7",
+        });
+    }
+
+    for post in &posts {
+        pages.push(Page {
+            path: blog_view::post_path(&post.slug),
+            lastmod: Some(post.date),
+            changefreq: "yearly",
+            priority: "0.6",
+        });
+    }
+    let mut tags: Vec<&str> = posts.iter().flat_map(|post| &post.tags).map(String::as_str).collect();
+    tags.sort_unstable();
+    tags.dedup();
+    for tag in tags {
+        pages.push(Page {
+            path: blog_view::tag_path(tag),
+            lastmod: posts
+                .iter()
+                .filter(|post| post.tags.iter().any(|t| t == tag))
+                .map(|post| post.date)
+                .max(),
+            changefreq: "weekly",
+            priority: "0.4",
+        });
+    }
+    pages
+}
+
+// Most recent change to a data file or any of its translations (`profile.en.json`)
+fn modified(data_dir: &Path, filename: &str) -> Option<NaiveDate> {
+    let stem = filename.strip_suffix(".json")?;
+    Locale::ALL
+        .into_iter()
+        .map(|locale| format!("{}.{}.json", stem, locale.code()))
+        .chain([filename.to_string()])
+        .filter_map(|name| files::modified(&data_dir.join(name)))
+        .max()
+        .map(|time| DateTime::<Utc>::from(time).date_naive())
+}
+
+fn urlset(config: &Config, pages: &[Page]) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\" xmlns:xhtml=\"http://www.w3.org/1999/xhtml\">\n",
+    );
+    for page in pages {
+        for locale in Locale::ALL {
+            let _ = writeln!(xml, "<url>\n<loc>{}</loc>", feed::escape(&config.url(&locale.path(&page.path))));
+            if let Some(lastmod) = page.lastmod {
+                let _ = writeln!(xml, "<lastmod>{}</lastmod>", lastmod.format("%Y-%m-%d"));
+            }
+            let _ = writeln!(xml, "<changefreq>{}</changefreq>", page.changefreq);
+            let _ = writeln!(xml, "<priority>{}</priority>", page.priority);
+            for alternate in Locale::ALL {
+                let _ = writeln!(
+                    xml,
+                    "<xhtml:link rel=\"alternate\" hreflang=\"{}\" href=\"{}\"/>",
+                    alternate.code(),
+                    feed::escape(&config.url(&alternate.path(&page.path)))
+                );
+            }
+            let _ = writeln!(
+                xml,
+                "<xhtml:link rel=\"alternate\" hreflang=\"x-default\" href=\"{}\"/>\n</url>",
+                feed::escape(&config.url(&page.path))
+            );
+        }
+    }
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+fn sitemap_index(config: &Config, count: usize) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <sitemapindex xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for number in 1..=count {
+        let _ = writeln!(
+            xml,
+            "<sitemap><loc>{}</loc></sitemap>",
+            feed::escape(&config.url(&format!("/sitemaps/{}.xml", number)))
+        );
+    }
+    xml.push_str("</sitemapindex>\n");
+    xml
+}
@@ -0,0 +1,245 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::Sha256;
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+// Bot protection for the contact form: a hidden honeypot field and a signed,
+// timestamped, single-use token embedded in every rendered form
+
+/// Hidden field humans never see; bots that fill every input give themselves away
+pub const HONEYPOT_FIELD: &str = "website";
+
+/// Whether the honeypot field was filled in, which only bots do
+pub fn is_honeypot_filled(value: &str) -> bool {
+    !value.trim().is_empty()
+}
+
+/// Nobody reads the page and types a message faster than this
+const MIN_FILL_TIME: Duration = Duration::from_secs(3);
+/// Forms left open longer than this must be reloaded
+const MAX_TOKEN_AGE: Duration = Duration::from_secs(2 * 60 * 60);
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TokenError {
+    Malformed,
+    BadSignature,
+    TooFast,
+    Expired,
+    Replayed,
+}
+
+impl TokenError {
+    /// Short identifier for metrics labels
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenError::Malformed => "malformed",
+            TokenError::BadSignature => "bad_signature",
+            TokenError::TooFast => "too_fast",
+            TokenError::Expired => "expired",
+            TokenError::Replayed => "replayed",
+        }
+    }
+}
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            TokenError::Malformed => "malformed token",
+            TokenError::BadSignature => "invalid signature",
+            TokenError::TooFast => "submitted too fast",
+            TokenError::Expired => "token expired",
+            TokenError::Replayed => "token already used",
+        };
+        f.write_str(reason)
+    }
+}
+
+/// Issues and verifies form tokens of the form `timestamp.nonce.signature`
+pub struct FormTokens {
+    key: [u8; 32],
+    /// Nonces of accepted submissions with their issue time, kept until they expire
+    used: Mutex<HashMap<String, u64>>,
+}
+
+impl FormTokens {
+    pub fn new(key: [u8; 32]) -> Self {
+        FormTokens {
+            key,
+            used: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn issue(&self) -> String {
+        self.issue_at(now_secs())
+    }
+
+    fn issue_at(&self, issued_at: u64) -> String {
+        let mut nonce = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let payload = format!("{}.{}", issued_at, URL_SAFE_NO_PAD.encode(nonce));
+        let signature = URL_SAFE_NO_PAD.encode(self.sign(&payload));
+        format!("{}.{}", payload, signature)
+    }
+
+    /// Check signature and age without using the token up, so a form
+    /// re-rendered with validation errors can be submitted again
+    pub fn verify(&self, token: &str) -> Result<(), TokenError> {
+        self.check(token).map(|_| ())
+    }
+
+    /// Verify and mark the token as used; a second submission with it is rejected
+    pub fn consume(&self, token: &str) -> Result<(), TokenError> {
+        let (issued_at, nonce) = self.check(token)?;
+
+        let mut used = self.lock_used();
+        let oldest_valid = now_secs().saturating_sub(MAX_TOKEN_AGE.as_secs());
+        used.retain(|_, issued| *issued >= oldest_valid);
+        if used.insert(nonce.to_string(), issued_at).is_some() {
+            return Err(TokenError::Replayed);
+        }
+        Ok(())
+    }
+
+    fn check<'a>(&self, token: &'a str) -> Result<(u64, &'a str), TokenError> {
+        let (issued_at, nonce) = self.parse(token)?;
+
+        let age = Duration::from_secs(now_secs().saturating_sub(issued_at));
+        if age < MIN_FILL_TIME {
+            return Err(TokenError::TooFast);
+        }
+        if age > MAX_TOKEN_AGE {
+            return Err(TokenError::Expired);
+        }
+        if self.lock_used().contains_key(nonce) {
+            return Err(TokenError::Replayed);
+        }
+        Ok((issued_at, nonce))
+    }
+
+    fn parse<'a>(&self, token: &'a str) -> Result<(u64, &'a str), TokenError> {
+        let (payload, signature) = token.rsplit_once('.').ok_or(TokenError::Malformed)?;
+        let (issued_at, nonce) = payload.split_once('.').ok_or(TokenError::Malformed)?;
+        let issued_at: u64 = issued_at.parse().map_err(|_| TokenError::Malformed)?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| TokenError::Malformed)?;
+
+        self.mac(payload)
+            .verify_slice(&signature)
+            .map_err(|_| TokenError::BadSignature)?;
+        Ok((issued_at, nonce))
+    }
+
+    fn sign(&self, payload: &str) -> Vec<u8> {
+        self.mac(payload).finalize().into_bytes().to_vec()
+    }
+
+    fn mac(&self, payload: &str) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts any key length");
+        mac.update(b"contact-form:");
+        mac.update(payload.as_bytes());
+        mac
+    }
+
+    fn lock_used(&self) -> std::sync::MutexGuard<'_, HashMap<String, u64>> {
+        self.used.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens() -> FormTokens {
+        FormTokens::new([7; 32])
+    }
+
+    // A token issued `age` ago
+    fn issued_ago(tokens: &FormTokens, age: Duration) -> String {
+        tokens.issue_at(now_secs() - age.as_secs())
+    }
+
+    #[test]
+    fn accepts_token_after_minimum_fill_time() {
+        let tokens = tokens();
+        let token = issued_ago(&tokens, MIN_FILL_TIME);
+        assert_eq!(tokens.verify(&token), Ok(()));
+        assert_eq!(tokens.consume(&token), Ok(()));
+    }
+
+    #[test]
+    fn rejects_token_submitted_too_fast() {
+        let tokens = tokens();
+        assert_eq!(tokens.verify(&tokens.issue()), Err(TokenError::TooFast));
+        let token = issued_ago(&tokens, Duration::from_secs(1));
+        assert_eq!(tokens.consume(&token), Err(TokenError::TooFast));
+    }
+
+    #[test]
+    fn rejects_expired_token() {
+        let tokens = tokens();
+        let token = issued_ago(&tokens, MAX_TOKEN_AGE - Duration::from_secs(60));
+        assert_eq!(tokens.verify(&token), Ok(()));
+        let token = issued_ago(&tokens, MAX_TOKEN_AGE + Duration::from_secs(1));
+        assert_eq!(tokens.verify(&token), Err(TokenError::Expired));
+    }
+
+    #[test]
+    fn rejects_replayed_token() {
+        let tokens = tokens();
+        let token = issued_ago(&tokens, Duration::from_secs(60));
+        // Verifying leaves the token usable, for forms sent back with errors
+        assert_eq!(tokens.verify(&token), Ok(()));
+        assert_eq!(tokens.consume(&token), Ok(()));
+        assert_eq!(tokens.consume(&token), Err(TokenError::Replayed));
+        assert_eq!(tokens.verify(&token), Err(TokenError::Replayed));
+        // Other tokens are unaffected
+        assert_eq!(tokens.consume(&issued_ago(&tokens, Duration::from_secs(60))), Ok(()));
+    }
+
+    #[test]
+    fn rejects_tampered_token() {
+        let tokens = tokens();
+        let token = issued_ago(&tokens, Duration::from_secs(60));
+        let (issued_at, rest) = token.split_once('.').unwrap();
+
+        // Backdated to look older than it is
+        let backdated = format!("{}.{}", issued_at.parse::<u64>().unwrap() - 10, rest);
+        assert_eq!(tokens.verify(&backdated), Err(TokenError::BadSignature));
+
+        let (payload, _) = token.rsplit_once('.').unwrap();
+        let forged = format!("{}.{}", payload, URL_SAFE_NO_PAD.encode([0u8; 32]));
+        assert_eq!(tokens.verify(&forged), Err(TokenError::BadSignature));
+
+        // Signed with another key
+        assert_eq!(FormTokens::new([8; 32]).verify(&token), Err(TokenError::BadSignature));
+    }
+
+    #[test]
+    fn rejects_malformed_token() {
+        let tokens = tokens();
+        for token in ["", "abc", "1.2", "now.nonce.c2ln", "1700000000.nonce.not base64!"] {
+            assert_eq!(tokens.verify(token), Err(TokenError::Malformed), "{:?}", token);
+        }
+    }
+
+    #[test]
+    fn honeypot() {
+        assert!(!is_honeypot_filled(""));
+        assert!(!is_honeypot_filled("  "));
+        assert!(is_honeypot_filled("https://spam.example"));
+    }
+}
@@ -0,0 +1,31 @@
+use crate::auth::AdminCredentials;
+use crate::config::Config;
+use crate::content::ContentStore;
+use crate::csrf::Csrf;
+use crate::messages::MessageStore;
+use crate::notifier::Notifiers;
+use crate::render_cache::RenderCache;
+use crate::sitemap::SitemapCache;
+use crate::spam::FormTokens;
+use metrics_exporter_prometheus::PrometheusHandle;
+use std::sync::Arc;
+
+// Shared application state handed to every handler
+#[derive(Clone)]
+pub struct AppState {
+    pub config: Arc<Config>,
+    pub content: Arc<ContentStore>,
+    pub messages: MessageStore,
+    pub notifiers: Notifiers,
+    /// Signs and checks the anti-spam token embedded in the contact form
+    pub form_tokens: Arc<FormTokens>,
+    pub csrf: Arc<Csrf>,
+    /// Rebuilt on the first request after a content reload
+    pub sitemap: Arc<SitemapCache>,
+    /// Rendered pages, dropped on the first request after a content reload
+    pub render_cache: Arc<RenderCache>,
+    /// Renders the collected metrics for /metrics
+    pub metrics: PrometheusHandle,
+    /// None when no admin password is configured (admin pages disabled)
+    pub admin: Option<Arc<AdminCredentials>>,
+}
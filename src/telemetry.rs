@@ -0,0 +1,189 @@
+use crate::config::{Config, LogFormat};
+use crate::routes::Route;
+use crate::state::AppState;
+use axum::{
+    Router,
+    body::Body,
+    extract::{ConnectInfo, MatchedPath, OriginalUri, State},
+    http::{HeaderValue, Request, header::CONTENT_TYPE},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use metrics_exporter_prometheus::{BuildError, Matcher, PrometheusBuilder, PrometheusHandle};
+use rand::RngCore;
+use std::{net::SocketAddr, time::Instant};
+use tower_http::{
+    LatencyUnit,
+    request_id::{MakeRequestId, PropagateRequestIdLayer, RequestId, SetRequestIdLayer},
+    trace::{DefaultOnResponse, TraceLayer},
+};
+use tracing::{Level, Span, field};
+use tracing_subscriber::EnvFilter;
+
+// Logging and metrics setup, and the request tracing and metrics middleware
+// applied to every route
+
+const REQUESTS_TOTAL: &str = "http_requests_total";
+const REQUEST_DURATION: &str = "http_request_duration_seconds";
+const CONTACT_SUBMISSIONS: &str = "contact_submissions_total";
+
+// Seconds; most pages render in well under a millisecond
+const LATENCY_BUCKETS: &[f64] = &[0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5];
+
+/// Install the global subscriber. RUST_LOG, when set, takes precedence over the configured level.
+pub fn init(config: &Config) {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| {
+        // Dependencies only report problems, this crate and the request log follow the config
+        EnvFilter::new(format!("warn,basic_web={0},tower_http={0}", config.log_level))
+    });
+
+    let subscriber = tracing_subscriber::fmt().with_env_filter(filter);
+    match config.log_format {
+        LogFormat::Pretty => subscriber.init(),
+        LogFormat::Json => subscriber.json().flatten_event(true).with_span_list(false).init(),
+    }
+}
+
+/// Request IDs, method, URI, client, htmx headers, status and latency for every
+/// request handled by `router`, its fallback included
+pub fn trace_requests<S>(router: Router<S>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    // Layers run from the last added to the first: the ID is set before the span is created
+    router
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(make_span)
+                .on_response(
+                    DefaultOnResponse::new()
+                        .level(Level::INFO)
+                        .latency_unit(LatencyUnit::Millis),
+                ),
+        )
+        .layer(SetRequestIdLayer::x_request_id(RandomRequestId))
+}
+
+fn make_span(request: &Request<Body>) -> Span {
+    let header = |name: &str| request.headers().get(name).and_then(|v| v.to_str().ok());
+    // The address as requested, before the language prefix was removed
+    let uri = request
+        .extensions()
+        .get::<OriginalUri>()
+        .map_or(request.uri(), |OriginalUri(uri)| uri);
+    let client_ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+
+    let span = tracing::info_span!(
+        "request",
+        id = header("x-request-id").unwrap_or("-"),
+        method = %request.method(),
+        uri = %uri,
+        client_ip = field::Empty,
+        htmx = header("hx-request").is_some(),
+        hx_target = field::Empty,
+        hx_trigger = field::Empty,
+    );
+    if let Some(ip) = client_ip {
+        span.record("client_ip", field::display(ip));
+    }
+    if let Some(target) = header("hx-target") {
+        span.record("hx_target", target);
+    }
+    if let Some(trigger) = header("hx-trigger") {
+        span.record("hx_trigger", trigger);
+    }
+    span
+}
+
+/// 16 hex characters, kept as is when a proxy already sent an `x-request-id`
+#[derive(Clone, Copy)]
+struct RandomRequestId;
+
+impl MakeRequestId for RandomRequestId {
+    fn make_request_id<B>(&mut self, _request: &Request<B>) -> Option<RequestId> {
+        let mut bytes = [0u8; 8];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let id: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        HeaderValue::from_str(&id).ok().map(RequestId::new)
+    }
+}
+
+/// Install the global metrics recorder; the handle renders the Prometheus text format
+pub fn install_metrics_recorder() -> Result<PrometheusHandle, BuildError> {
+    PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Full(REQUEST_DURATION.to_string()), LATENCY_BUCKETS)?
+        .install_recorder()
+}
+
+/// Middleware counting requests and timing them, labeled by route, method,
+/// status and render mode (htmx partial or full layout)
+pub async fn track_metrics(request: Request<Body>, next: Next) -> Response {
+    let start = Instant::now();
+    let route = route_label(&request);
+    let method = request.method().to_string();
+    let mode = if request.headers().contains_key("hx-request") { "htmx" } else { "full" };
+
+    let response = next.run(request).await;
+
+    let status = response.status().as_u16().to_string();
+    metrics::counter!(
+        REQUESTS_TOTAL,
+        "route" => route.clone(),
+        "method" => method.clone(),
+        "mode" => mode,
+        "status" => status,
+    )
+    .increment(1);
+    metrics::histogram!(REQUEST_DURATION, "route" => route, "method" => method, "mode" => mode)
+        .record(start.elapsed().as_secs_f64());
+    response
+}
+
+// Route variant name, or the matched path template for the other endpoints,
+// so labels stay bounded whatever URLs clients request
+fn route_label(request: &Request<Body>) -> String {
+    // Nested services such as /static don't record a matched path
+    let Some(matched) = request.extensions().get::<MatchedPath>() else {
+        return if request.uri().path().starts_with("/static/") { "static" } else { "not_found" }.to_string();
+    };
+    match Route::from_pattern(matched.as_str()) {
+        Some(route) => route.name().to_string(),
+        None => matched.as_str().to_string(),
+    }
+}
+
+pub async fn serve_metrics(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [(CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+        state.metrics.render(),
+    )
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ContactOutcome {
+    Success,
+    /// One per field that failed validation, with the field as reason
+    Invalid,
+    /// Honeypot filled or form token rejected
+    Spam,
+    Error,
+}
+
+impl ContactOutcome {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ContactOutcome::Success => "success",
+            ContactOutcome::Invalid => "invalid",
+            ContactOutcome::Spam => "spam",
+            ContactOutcome::Error => "error",
+        }
+    }
+}
+
+pub fn record_contact_submission(outcome: ContactOutcome, reason: &'static str) {
+    metrics::counter!(CONTACT_SUBMISSIONS, "outcome" => outcome.as_str(), "reason" => reason).increment(1);
+}
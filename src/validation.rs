@@ -0,0 +1,214 @@
+use crate::i18n::Locale;
+use std::collections::BTreeMap;
+
+// Reusable form validation rules producing per-field errors
+
+/// A failed rule, translated when the form is rendered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldError {
+    Required,
+    MinChars(usize),
+    MaxChars(usize),
+    ControlChars,
+    InvalidEmail,
+}
+
+impl FieldError {
+    pub fn message(&self, locale: Locale) -> String {
+        match self {
+            FieldError::Required => locale.t("validation.required").to_string(),
+            FieldError::MinChars(min) => locale.t_with("validation.min_chars", &[("min", &min.to_string())]),
+            FieldError::MaxChars(max) => locale.t_with("validation.max_chars", &[("max", &max.to_string())]),
+            FieldError::ControlChars => locale.t("validation.control_chars").to_string(),
+            FieldError::InvalidEmail => locale.t("validation.email").to_string(),
+        }
+    }
+}
+
+/// Errors keyed by form field name; only the first error per field is kept
+#[derive(Debug, Default, Clone)]
+pub struct FieldErrors {
+    errors: BTreeMap<&'static str, FieldError>,
+}
+
+impl FieldErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, field: &'static str, error: FieldError) {
+        self.errors.entry(field).or_insert(error);
+    }
+
+    pub fn get(&self, field: &str) -> Option<FieldError> {
+        self.errors.get(field).copied()
+    }
+
+    pub fn has(&self, field: &str) -> bool {
+        self.errors.contains_key(field)
+    }
+
+    /// Names of the fields with an error
+    pub fn fields(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.errors.keys().copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Ok(value) when no rule failed
+    pub fn into_result<T>(self, value: T) -> Result<T, FieldErrors> {
+        if self.is_empty() { Ok(value) } else { Err(self) }
+    }
+}
+
+/// Length in characters, not bytes ("ñ" counts as one)
+pub fn char_count(value: &str) -> usize {
+    value.chars().count()
+}
+
+pub fn required(errors: &mut FieldErrors, field: &'static str, value: &str) {
+    if value.trim().is_empty() {
+        errors.add(field, FieldError::Required);
+    }
+}
+
+pub fn min_chars(errors: &mut FieldErrors, field: &'static str, value: &str, min: usize) {
+    if char_count(value) < min {
+        errors.add(field, FieldError::MinChars(min));
+    }
+}
+
+pub fn max_chars(errors: &mut FieldErrors, field: &'static str, value: &str, max: usize) {
+    if char_count(value) > max {
+        errors.add(field, FieldError::MaxChars(max));
+    }
+}
+
+/// Reject control characters; multi-line fields may keep newlines and tabs
+pub fn no_control_chars(errors: &mut FieldErrors, field: &'static str, value: &str, multiline: bool) {
+    let allowed = |c: char| multiline && matches!(c, '\n' | '\r' | '\t');
+    if value.chars().any(|c| c.is_control() && !allowed(c)) {
+        errors.add(field, FieldError::ControlChars);
+    }
+}
+
+pub fn email(errors: &mut FieldErrors, field: &'static str, value: &str) {
+    if !is_valid_email(value) {
+        errors.add(field, FieldError::InvalidEmail);
+    }
+}
+
+/// Practical address check: a dot-atom local part and a domain with at least
+/// two labels, without going into quoted strings or IP literals.
+pub fn is_valid_email(value: &str) -> bool {
+    if value.len() > 254 {
+        return false;
+    }
+    let Some((local, domain)) = value.rsplit_once('@') else {
+        return false;
+    };
+
+    let local_ok = !local.is_empty()
+        && local.len() <= 64
+        && local.split('.').all(|part| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+/=?^_`{|}~-".contains(c))
+        });
+
+    let labels: Vec<&str> = domain.split('.').collect();
+    let domain_ok = labels.len() >= 2
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && label.chars().count() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_alphanumeric() || c == '-')
+        })
+        && labels
+            .last()
+            .is_some_and(|tld| tld.chars().count() >= 2 && tld.chars().all(char::is_alphabetic));
+
+    local_ok && domain_ok
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(rule: impl FnOnce(&mut FieldErrors)) -> Option<FieldError> {
+        let mut errors = FieldErrors::new();
+        rule(&mut errors);
+        errors.get("field")
+    }
+
+    #[test]
+    fn required_rejects_blank_values() {
+        assert_eq!(check(|e| required(e, "field", "")), Some(FieldError::Required));
+        assert_eq!(check(|e| required(e, "field", " \n\t")), Some(FieldError::Required));
+        assert_eq!(check(|e| required(e, "field", "Ada")), None);
+    }
+
+    #[test]
+    fn lengths_count_characters() {
+        assert_eq!(check(|e| min_chars(e, "field", "ñandú", 5)), None);
+        assert_eq!(check(|e| min_chars(e, "field", "ñand", 5)), Some(FieldError::MinChars(5)));
+        assert_eq!(check(|e| max_chars(e, "field", "ñandú", 5)), None);
+        assert_eq!(check(|e| max_chars(e, "field", "ñandús", 5)), Some(FieldError::MaxChars(5)));
+    }
+
+    #[test]
+    fn control_chars_allow_line_breaks_only_when_multiline() {
+        assert_eq!(check(|e| no_control_chars(e, "field", "a\nb\r\n\tc", true)), None);
+        assert_eq!(check(|e| no_control_chars(e, "field", "a\nb", false)), Some(FieldError::ControlChars));
+        assert_eq!(check(|e| no_control_chars(e, "field", "a\u{0}b", true)), Some(FieldError::ControlChars));
+        assert_eq!(check(|e| no_control_chars(e, "field", "a\u{1b}[31mb", true)), Some(FieldError::ControlChars));
+    }
+
+    #[test]
+    fn accepts_common_addresses() {
+        assert!(is_valid_email("ada@example.com"));
+        assert!(is_valid_email("first.last+tag@mail.example.co"));
+        assert!(is_valid_email("o'brien@example.org"));
+        assert!(is_valid_email("ada@correo.españa.es"));
+    }
+
+    #[test]
+    fn rejects_invalid_addresses() {
+        let too_long = format!("{}@example.com", "a".repeat(65));
+        for address in [
+            "",
+            "ada",
+            "ada@",
+            "@example.com",
+            "ada@localhost",
+            "ada@example.c",
+            "ada@example.123",
+            "ada..lovelace@example.com",
+            ".ada@example.com",
+            "ada lovelace@example.com",
+            "ada@-example.com",
+            "ada@example..com",
+            "josé@example.com",
+            too_long.as_str(),
+        ] {
+            assert!(!is_valid_email(address), "{:?}", address);
+        }
+    }
+
+    #[test]
+    fn keeps_first_error_per_field() {
+        let mut errors = FieldErrors::new();
+        required(&mut errors, "message", "");
+        min_chars(&mut errors, "message", "", 10);
+        email(&mut errors, "email", "nope");
+        assert_eq!(errors.get("message"), Some(FieldError::Required));
+        assert!(errors.has("email"));
+        assert_eq!(errors.fields().collect::<Vec<_>>(), ["email", "message"]);
+        assert!(errors.into_result(()).is_err());
+        assert!(matches!(FieldErrors::new().into_result(1), Ok(1)));
+    }
+}
@@ -0,0 +1,133 @@
+use maud::{html, Markup};
+use crate::csrf::CsrfToken;
+use crate::i18n::Locale;
+use crate::messages::{MessageStatus, StoredMessage};
+use crate::routes::Route;
+use crate::views::page::{Page, PageMeta};
+
+pub fn render_inbox(messages: &[StoredMessage], locale: Locale) -> Page {
+    let unread = messages.iter().filter(|m| m.status == MessageStatus::New).count();
+    let meta = PageMeta::new(locale.t("admin.title"), "", Route::AdminMessages.path()).noindex();
+
+    let markup = html! {
+        section class="section" {
+            div class="container" {
+                div class="content-card" {
+                    h2 class="admin-title" { (locale.t("admin.title")) }
+                    p class="admin-summary" {
+                        (locale.t_with(
+                            "admin.summary",
+                            &[("total", &messages.len().to_string()), ("unread", &unread.to_string())],
+                        ))
+                    }
+
+                    @if messages.is_empty() {
+                        p class="admin-empty" { (locale.t("admin.empty")) }
+                    } @else {
+                        table class="admin-table" {
+                            thead {
+                                tr {
+                                    th { (locale.t("admin.date")) }
+                                    th { (locale.t("admin.name")) }
+                                    th { (locale.t("admin.email")) }
+                                    th { (locale.t("admin.status")) }
+                                }
+                            }
+                            tbody {
+                                @for message in messages {
+                                    tr class={ "admin-row admin-row-" (message.status.as_str()) } {
+                                        td { (message.created_at.format("%Y-%m-%d %H:%M")) }
+                                        td {
+                                            a href=(locale.path(&message_path(message.id)))
+                                              hx-get=(locale.path(&message_path(message.id)))
+                                              hx-target="main"
+                                              hx-push-url="true"
+                                            { (message.name) }
+                                        }
+                                        td { (message.email) }
+                                        td { (status_badge(message.status, locale)) }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    Page::new(meta, markup)
+}
+
+pub fn render_message(message: &StoredMessage, csrf: &CsrfToken, locale: Locale) -> Page {
+    let path = locale.path(&message_path(message.id));
+    let title = locale.t_with("admin.message_from", &[("name", &message.name)]);
+    let meta = PageMeta::new(title, "", message_path(message.id)).noindex();
+
+    let markup = html! {
+        section class="section" {
+            div class="container" {
+                div class="content-card" {
+                    a href=(locale.path(&Route::AdminMessages.path()))
+                      hx-get=(locale.path(&Route::AdminMessages.path()))
+                      hx-target="main"
+                      hx-push-url="true"
+                      class="admin-back-link"
+                    { (locale.t("admin.back")) }
+
+                    h2 class="admin-title" { (locale.t_with("admin.message_from", &[("name", &message.name)])) }
+
+                    dl class="admin-details" {
+                        dt { (locale.t("admin.email")) }
+                        dd { a href={ "mailto:" (message.email) } { (message.email) } }
+                        dt { (locale.t("admin.received")) }
+                        dd { (message.created_at.format("%Y-%m-%d %H:%M:%S UTC")) }
+                        dt { (locale.t("admin.status")) }
+                        dd { (status_badge(message.status, locale)) }
+                        dt { (locale.t("admin.ip")) }
+                        dd { (message.ip.as_deref().unwrap_or("-")) }
+                        dt { (locale.t("admin.user_agent")) }
+                        dd { (message.user_agent.as_deref().unwrap_or("-")) }
+                    }
+
+                    div class="admin-message-body" { (message.message) }
+
+                    div class="admin-actions" {
+                        @if message.status == MessageStatus::New {
+                            form method="post" action={ (path) "/handled" }
+                                 hx-post={ (path) "/handled" }
+                                 hx-target="main"
+                            {
+                                (csrf.hidden_field())
+                                button type="submit" class="cta-button" { (locale.t("admin.mark_handled")) }
+                            }
+                        }
+                        form method="post" action={ (path) "/delete" }
+                             hx-post={ (path) "/delete" }
+                             hx-target="main"
+                             hx-confirm=(locale.t("admin.delete_confirm"))
+                        {
+                            (csrf.hidden_field())
+                            button type="submit" class="cta-button admin-delete-button" { (locale.t("admin.delete")) }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    Page::new(meta, markup)
+}
+
+pub fn message_path(id: i64) -> String {
+    format!("{}/{}", Route::AdminMessages.path(), id)
+}
+
+fn status_badge(status: MessageStatus, locale: Locale) -> Markup {
+    html! {
+        @match status {
+            MessageStatus::New => span class="tag admin-status-new" { (locale.t("admin.status_new")) },
+            MessageStatus::Handled => span class="tag admin-status-handled" { (locale.t("admin.status_handled")) },
+        }
+    }
+}
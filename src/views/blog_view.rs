@@ -0,0 +1,142 @@
+use maud::{html, Markup, PreEscaped};
+use crate::blog::{Post, PostPage};
+use crate::i18n::Locale;
+use crate::og;
+use crate::routes::Route;
+use crate::views::components::nav_link;
+use crate::views::page::{Page, PageMeta};
+
+pub fn post_path(slug: &str) -> String {
+    format!("{}/{}", Route::Blog.path(), slug)
+}
+
+pub fn tag_path(tag: &str) -> String {
+    format!("{}/tags/{}", Route::Blog.path(), tag)
+}
+
+/// Post list, all posts or those with `current_tag`
+pub fn render_index(page: &PostPage, tags: &[(&str, usize)], current_tag: Option<&str>, locale: Locale) -> Page {
+    let base = current_tag.map_or_else(|| Route::Blog.path().to_string(), tag_path);
+    let page_path = |number: usize| {
+        if number == 1 { base.clone() } else { format!("{}?page={}", base, number) }
+    };
+    let page_link = |number: usize| locale.path(&page_path(number));
+
+    let title = match current_tag {
+        Some(tag) => locale.t_with("blog.tagged", &[("tag", tag)]),
+        None => locale.t("blog.title").to_string(),
+    };
+    let meta = PageMeta::new(title.as_str(), locale.t("blog.intro"), page_path(page.number))
+        .with_image(og::page_image_path(&Route::Blog));
+
+    let markup = html! {
+        section class="section" {
+            div class="container" {
+                div class="content-card" {
+                    h2 class="blog-title" { (title) }
+                    @if current_tag.is_some() {
+                        p class="blog-intro" { (nav_link(&locale.path(&Route::Blog.path()), locale.t("blog.all_posts"))) }
+                    } @else {
+                        p class="blog-intro" { (locale.t("blog.intro")) }
+                    }
+
+                    @if page.posts.is_empty() {
+                        p class="blog-empty" { (locale.t("blog.empty")) }
+                    }
+
+                    div class="blog-list" {
+                        @for post in page.posts {
+                            article class="blog-card" {
+                                h3 class="blog-card-title" {
+                                    (nav_link(&locale.path(&post_path(&post.slug)), &post.title))
+                                }
+                                (post_meta(post, locale))
+                                p class="blog-card-summary" { (post.summary) }
+                                (nav_link(&locale.path(&post_path(&post.slug)), locale.t("blog.read_more")))
+                            }
+                        }
+                    }
+
+                    @if page.total_pages > 1 {
+                        nav class="blog-pagination" aria-label=(locale.t("blog.pagination")) {
+                            @if page.number > 1 {
+                                (nav_link(&page_link(page.number - 1), locale.t("blog.previous")))
+                            }
+                            span class="blog-page-number" {
+                                (locale.t_with("blog.page", &[
+                                    ("number", &page.number.to_string()),
+                                    ("total", &page.total_pages.to_string()),
+                                ]))
+                            }
+                            @if page.number < page.total_pages {
+                                (nav_link(&page_link(page.number + 1), locale.t("blog.next")))
+                            }
+                        }
+                    }
+
+                    @if !tags.is_empty() {
+                        div class="blog-tags" {
+                            h3 class="blog-tags-title" { (locale.t("blog.tags")) }
+                            div class="project-tags" {
+                                @for (tag, count) in tags {
+                                    a href=(locale.path(&tag_path(tag)))
+                                      hx-get=(locale.path(&tag_path(tag)))
+                                      hx-target="main"
+                                      hx-push-url="true"
+                                      class={ @if current_tag == Some(*tag) { "tag active" } @else { "tag" } }
+                                    { (tag) " (" (count) ")" }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    Page::new(meta, markup)
+}
+
+pub fn render_post(post: &Post, locale: Locale) -> Page {
+    let mut meta = PageMeta::new(&post.title, &post.summary, post_path(&post.slug))
+        .with_image(og::image_path(&post.slug));
+    if post.draft {
+        meta = meta.noindex();
+    }
+
+    let markup = html! {
+        section class="section" {
+            div class="container" {
+                article class="content-card blog-post" {
+                    p { (nav_link(&locale.path(&Route::Blog.path()), locale.t("blog.all_posts"))) }
+                    h2 class="blog-title" { (post.title) }
+                    (post_meta(post, locale))
+                    div class="blog-post-body" {
+                        (PreEscaped(&post.html))
+                    }
+                }
+            }
+        }
+    };
+
+    Page::new(meta, markup)
+}
+
+fn post_meta(post: &Post, locale: Locale) -> Markup {
+    html! {
+        div class="blog-meta" {
+            time datetime=(post.date.format("%Y-%m-%d")) { (locale.format_date(post.date)) }
+            @if post.draft {
+                span class="blog-draft" { (locale.t("blog.draft")) }
+            }
+            @for tag in &post.tags {
+                a href=(locale.path(&tag_path(tag)))
+                  hx-get=(locale.path(&tag_path(tag)))
+                  hx-target="main"
+                  hx-push-url="true"
+                  class="tag"
+                { (tag) }
+            }
+        }
+    }
+}
@@ -0,0 +1,10 @@
+use maud::{html, Markup};
+
+// Small pieces of markup shared by several views
+
+/// Link that swaps the new page into <main> like the navigation links
+pub fn nav_link(href: &str, text: &str) -> Markup {
+    html! {
+        a href=(href) hx-get=(href) hx-target="main" hx-push-url="true" class="project-link" { (text) }
+    }
+}
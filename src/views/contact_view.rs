@@ -1,147 +1,242 @@
 use maud::{html, Markup};
+use crate::content::Content;
+use crate::csrf::CsrfToken;
+use crate::i18n::Locale;
+use crate::og;
+use crate::controllers::contact::{ContactForm, EMAIL_MAX_CHARS, MESSAGE_MAX_CHARS, NAME_MAX_CHARS};
 use crate::routes::Route;
+use crate::spam::HONEYPOT_FIELD;
+use crate::validation::FieldErrors;
+use crate::views::page::{Page, PageMeta};
 
-pub fn render() -> Markup {
-    html! {
+// Id of the block swapped by htmx when the form is submitted
+const FORM_BLOCK_ID: &str = "contact-form-block";
+
+/// Contact page with an empty form signed with `form_token`
+pub fn render(content: &Content, form_token: &str, csrf: &CsrfToken, locale: Locale) -> Page {
+    render_with_form(
+        content,
+        render_form(&ContactForm::default(), &FieldErrors::new(), form_token, csrf, locale),
+        locale,
+    )
+}
+
+/// Contact page with the given form block (empty form, form with errors or success message)
+pub fn render_with_form(content: &Content, form_block: Markup, locale: Locale) -> Page {
+    let profile = &content.profile;
+    let contact = &profile.contact;
+
+    let meta = PageMeta::new(locale.t("contact.title"), &profile.pitch, Route::Contact.path())
+        .with_image(og::page_image_path(&Route::Contact));
+
+    let markup = html! {
         section class="section" {
             div class="container" {
                 div class="content-card" {
-                    h2 class="contact-section-title" { "Contacto" }
+                    h2 class="contact-section-title" { (locale.t("contact.title")) }
                     
                     div class="contact-grid" {
                         div class="contact-info" {
-                            h3 { "¡Hablemos!" }
-                            p class="contact-info-text" {
-                                "¿Tienes un proyecto en mente? ¿Buscas un desarrollador para tu equipo? "
-                                "¡Me encantaría escuchar sobre tu idea y cómo podemos trabajar juntos!"
-                            }
+                            h3 { (locale.t("contact.intro")) }
+                            p class="contact-info-text" { (profile.pitch) }
                             
                             div class="contact-item" {
-                                strong { "📧 Email:" }
-                                span { "tu.email@ejemplo.com" }
+                                strong { (locale.t("contact.email")) }
+                                span { (contact.email) }
                             }
                             div class="contact-item" {
-                                strong { "📱 Teléfono:" }
-                                span { "+34 123 456 789" }
+                                strong { (locale.t("contact.phone")) }
+                                span { (contact.phone) }
                             }
                             div class="contact-item" {
-                                strong { "📍 Ubicación:" }
-                                span { "Madrid, España" }
+                                strong { (locale.t("contact.location")) }
+                                span { (contact.location) }
                             }
                             div class="contact-item" {
                                 strong { "💼 LinkedIn:" }
-                                a href="https://linkedin.com/in/tu-perfil" class="contact-link" { "linkedin.com/in/tu-perfil" }
+                                a href=(contact.linkedin) class="contact-link" { (display_url(&contact.linkedin)) }
                             }
                             div class="contact-item" {
                                 strong { "🐙 GitHub:" }
-                                a href="https://github.com/tu-usuario" class="contact-link" { "github.com/tu-usuario" }
+                                a href=(contact.github) class="contact-link" { (display_url(&contact.github)) }
                             }
                             
                             div class="contact-info-section" {
-                                h4 class="contact-info-section-title" { "¿Por qué trabajar conmigo?" }
-                                h4 class="contact-info-section-title" { "¿Por qué trabajar conmigo?" }
+                                h4 class="contact-info-section-title" { (locale.t("contact.why_me")) }
                                 ul class="contact-info-list" {
-                                    li class="contact-info-list-item" { "✅ Código limpio y bien documentado" }
-                                    li class="contact-info-list-item" { "✅ Comunicación clara y constante" }
-                                    li class="contact-info-list-item" { "✅ Entrega puntual de proyectos" }
-                                    li class="contact-list-item-custom" { "✅ Tecnologías modernas y escalables" }
-                                    li class="contact-list-item-custom" { "✅ Soporte post-lanzamiento" }
+                                    @for strength in &profile.strengths {
+                                        li class="contact-info-list-item" { (strength) }
+                                    }
                                 }
                             }
                         }
                         
                         div class="contact-form" {
-                            h3 class="contact-form-title" { "Envíame un mensaje" }
+                            h3 class="contact-form-title" { (locale.t("contact.form_title")) }
                             
-                            div id="contact-response" {
-                                // Response will be loaded here
-                            }
-                            
-                            form hx-post=(Route::Contact.path()) hx-target="#contact-response" hx-swap="innerHTML" {
-                                div class="form-group" {
-                                    label for="name" { "Nombre *" }
-                                    input type="text" id="name" name="name" required="true" placeholder="Tu nombre completo" autocomplete="name";
-                                }
-                                
-                                div class="form-group" {
-                                    label for="email" { "Email *" }
-                                    input type="email" id="email" name="email" required="true" placeholder="tu@email.com" autocomplete="email";
-                                }
-                                
-                                div class="form-group" {
-                                    label for="message" { "Mensaje *" }
-                                    textarea id="message" name="message" required="true" placeholder="Cuéntame sobre tu proyecto o cómo puedo ayudarte..." autocomplete="on" {}
-                                }
-                                
-                                button type="submit" class="cta-button contact-form-submit-button" {
-                                    "Enviar Mensaje"
-                                }
-                            }
+                            (form_block)
                             
                             p class="contact-form-footer-text" {
-                                "Te responderé en menos de 24 horas"
+                                (locale.t("contact.response_time"))
                             }
                         }
                     }
                     
                     div class="availability-section" {
-                        h3 class="availability-section-title" { "Disponibilidad" }
+                        h3 class="availability-section-title" { (locale.t("contact.availability")) }
                         div class="availability-grid" {
-                            div class="availability-item availability-item-available" {
-                                h4 class="availability-item-title" { "🟢 Disponible" }
-                                p class="availability-item-description" { "Para proyectos freelance" }
-                            }
-                            div class="availability-item availability-item-consultancy" {
-                                h4 class="availability-item-title" { "🟡 Consultoría" }
-                                p class="availability-item-description" { "Revisión de código y arquitectura" }
-                            }
-                            div class="availability-item availability-item-mentoring" {
-                                h4 class="availability-item-title" { "🔵 Mentoring" }
-                                p class="availability-item-description" { "Para desarrolladores junior" }
+                            @for item in &profile.availability {
+                                div class={ "availability-item availability-item-" (item.kind) } {
+                                    h4 class="availability-item-title" { (item.title) }
+                                    p class="availability-item-description" { (item.description) }
+                                }
                             }
                         }
                     }
                 }
             }
         }
-    }
+    };
+
+    Page::new(meta, markup)
+}
+
+// Show links without the scheme, e.g. "github.com/tu-usuario"
+fn display_url(url: &str) -> &str {
+    url.trim_start_matches("https://").trim_start_matches("http://")
+}
+
+/// The form refilled with the submitted values and an error next to each invalid field
+pub fn render_form(
+    form: &ContactForm,
+    errors: &FieldErrors,
+    form_token: &str,
+    csrf: &CsrfToken,
+    locale: Locale,
+) -> Markup {
+    render_form_block(form, errors, form_token, csrf, locale, None)
 }
 
-pub fn render_success(name: &str) -> Markup {
+pub fn render_success(name: &str, content: &Content, locale: Locale) -> Markup {
+    let contact = &content.profile.contact;
+
     html! {
-        div class="alert alert-success" {
-            h4 class="alert-title" { "¡Mensaje enviado con éxito!" }
-            p { "Gracias " (name) ", he recibido tu mensaje y te responderé muy pronto." }
-            p class="alert-text-mt" { 
-                "Mientras tanto, puedes seguirme en "
-                a href="https://linkedin.com/in/tu-perfil" class="alert-link" { "LinkedIn" }
-                " o revisar mis proyectos en "
-                a href="https://github.com/tu-usuario" class="contact-github-link" { "GitHub" }
-                "."
+        div id=(FORM_BLOCK_ID) {
+            div class="alert alert-success" {
+                h4 class="alert-title" { (locale.t("contact.success_title")) }
+                p { (locale.t_with("contact.success_text", &[("name", name)])) }
+                p class="alert-text-mt" { 
+                    (locale.t("contact.success_follow")) " "
+                    a href=(contact.linkedin) class="alert-link" { "LinkedIn" }
+                    " " (locale.t("contact.success_or")) " "
+                    a href=(contact.github) class="contact-github-link" { "GitHub" }
+                    "."
+                }
             }
-        }
-        
-        div class="alert-action-container" {
-            p { "¿Quieres enviar otro mensaje?" }
-            button hx-get=(Route::Contact.path()) hx-target="#contact-response" hx-swap="innerHTML" class="cta-button alert-button" {
-                "Nuevo mensaje"
+            
+            div class="alert-action-container" {
+                p { (locale.t("contact.another")) }
+                a href=(locale.path(&Route::Contact.path())) hx-get=(locale.path(&Route::Contact.path())) hx-target="main" class="cta-button alert-button" {
+                    (locale.t("contact.new_message"))
+                }
             }
         }
     }
 }
 
-pub fn render_error(error_message: &str) -> Markup {
-    html! {
+/// The form refilled with the submitted values under an error that isn't tied to a field
+pub fn render_error(
+    error_message: &str,
+    form: &ContactForm,
+    form_token: &str,
+    csrf: &CsrfToken,
+    locale: Locale,
+) -> Markup {
+    let alert = html! {
         div class="alert alert-error" {
-            h4 class="alert-title" { "Error al enviar el mensaje" }
+            h4 class="alert-title" { (locale.t("contact.error_title")) }
             p { (error_message) }
         }
-        
-        div class="alert-action-container" {
-            p { "Por favor, corrige el error e inténtalo de nuevo:" }
-            button hx-get=(Route::Contact.path()) hx-target="#contact-response" hx-swap="innerHTML" class="cta-button alert-button" {
-                "Reintentar"
+    };
+    render_form_block(form, &FieldErrors::new(), form_token, csrf, locale, Some(alert))
+}
+
+fn render_form_block(
+    form: &ContactForm,
+    errors: &FieldErrors,
+    form_token: &str,
+    csrf: &CsrfToken,
+    locale: Locale,
+    alert: Option<Markup>,
+) -> Markup {
+    html! {
+        div id=(FORM_BLOCK_ID) {
+            @if let Some(alert) = alert {
+                (alert)
+            } @else if !errors.is_empty() {
+                div class="alert alert-error" role="alert" {
+                    p { (locale.t("contact.fix_fields")) }
+                }
             }
+
+            form method="post" action=(locale.path(&Route::Contact.path()))
+                 hx-post=(locale.path(&Route::Contact.path()))
+                 hx-target={ "#" (FORM_BLOCK_ID) }
+                 hx-swap="outerHTML"
+            {
+                (csrf.hidden_field())
+                input type="hidden" name="form_token" value=(form_token);
+
+                // Honeypot: hidden with CSS and from assistive tech, only bots fill it in
+                div class="form-hp" aria-hidden="true" {
+                    label for=(HONEYPOT_FIELD) { (locale.t("contact.honeypot")) }
+                    input type="text" id=(HONEYPOT_FIELD) name=(HONEYPOT_FIELD)
+                          tabindex="-1" autocomplete="off";
+                }
+
+                div.form-group.has-error[errors.has("name")] {
+                    label for="name" { (locale.t("contact.name")) }
+                    input type="text" id="name" name="name" value=(form.name)
+                          required="true" maxlength=(NAME_MAX_CHARS)
+                          placeholder=(locale.t("contact.name_placeholder")) autocomplete="name"
+                          aria-invalid=[errors.has("name").then_some("true")]
+                          aria-describedby=[errors.has("name").then_some("name-error")];
+                    (field_error("name", errors, locale))
+                }
+                
+                div.form-group.has-error[errors.has("email")] {
+                    label for="email" { (locale.t("contact.email_label")) }
+                    input type="email" id="email" name="email" value=(form.email)
+                          required="true" maxlength=(EMAIL_MAX_CHARS)
+                          placeholder=(locale.t("contact.email_placeholder")) autocomplete="email"
+                          aria-invalid=[errors.has("email").then_some("true")]
+                          aria-describedby=[errors.has("email").then_some("email-error")];
+                    (field_error("email", errors, locale))
+                }
+                
+                div.form-group.has-error[errors.has("message")] {
+                    label for="message" { (locale.t("contact.message")) }
+                    textarea id="message" name="message"
+                             required="true" maxlength=(MESSAGE_MAX_CHARS)
+                             placeholder=(locale.t("contact.message_placeholder")) autocomplete="on"
+                             aria-invalid=[errors.has("message").then_some("true")]
+                             aria-describedby=[errors.has("message").then_some("message-error")]
+                    { (form.message) }
+                    (field_error("message", errors, locale))
+                }
+                
+                button type="submit" class="cta-button contact-form-submit-button" {
+                    (locale.t("contact.submit"))
+                }
+            }
+        }
+    }
+}
+
+fn field_error(field: &str, errors: &FieldErrors, locale: Locale) -> Markup {
+    html! {
+        @if let Some(error) = errors.get(field) {
+            span class="field-error" id={ (field) "-error" } { (error.message(locale)) }
         }
     }
 }
\ No newline at end of file
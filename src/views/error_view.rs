@@ -1,38 +1,143 @@
+use axum::{
+    http::{HeaderName, StatusCode},
+    response::{Html, IntoResponse, Response},
+};
 use maud::{html, Markup, DOCTYPE};
+use crate::assets::asset_url;
+use crate::config::Config;
+use crate::i18n::Locale;
 use crate::routes::Route;
 
-pub fn not_found() -> Markup {
+// Code, catalog keys of the title and description, icon
+const NOT_FOUND: (&str, &str, &str, &str) = (
+    "404",
+    "error.not_found_title",
+    "error.not_found_text",
+    "🔍",
+);
+
+/// Error for an htmx request: just the message (one of the `*_content` views), swapped
+/// into <main> instead of the original target
+pub fn htmx_error(status: StatusCode, content: Markup) -> Response {
+    (
+        status,
+        [
+            (HeaderName::from_static("hx-retarget"), "main"),
+            (HeaderName::from_static("hx-reswap"), "innerHTML"),
+        ],
+        Html(content.into_string()),
+    )
+        .into_response()
+}
+
+pub fn not_found(config: &Config, locale: Locale) -> Markup {
+    let (code, title, description, icon) = NOT_FOUND;
+    render_error_page(config, locale, code, title, description, icon)
+}
+
+/// Same message without the surrounding document, for htmx requests
+pub fn not_found_content(locale: Locale) -> Markup {
+    let (code, title, description, icon) = NOT_FOUND;
+    render_error_content(locale, code, title, description, icon)
+}
+
+pub fn server_error(config: &Config, locale: Locale) -> Markup {
+    render_error_page(
+        config,
+        locale,
+        "500",
+        "error.server_error_title",
+        "error.server_error_text",
+        "🛠️",
+    )
+}
+
+const FORBIDDEN: (&str, &str, &str, &str) = (
+    "403",
+    "error.forbidden_title",
+    "error.forbidden_text",
+    "🔒",
+);
+
+pub fn forbidden(config: &Config, locale: Locale) -> Markup {
+    let (code, title, description, icon) = FORBIDDEN;
+    render_error_page(config, locale, code, title, description, icon)
+}
+
+/// Same message without the surrounding document, for htmx requests
+pub fn forbidden_content(locale: Locale) -> Markup {
+    let (code, title, description, icon) = FORBIDDEN;
+    render_error_content(locale, code, title, description, icon)
+}
+
+const TOO_MANY_REQUESTS: (&str, &str, &str, &str) = (
+    "429",
+    "error.too_many_requests_title",
+    "error.too_many_requests_text",
+    "⏳",
+);
+
+pub fn too_many_requests(config: &Config, locale: Locale) -> Markup {
+    let (code, title, description, icon) = TOO_MANY_REQUESTS;
+    render_error_page(config, locale, code, title, description, icon)
+}
+
+/// Same message without the surrounding document, for htmx requests
+pub fn too_many_requests_content(locale: Locale) -> Markup {
+    let (code, title, description, icon) = TOO_MANY_REQUESTS;
+    render_error_content(locale, code, title, description, icon)
+}
+
+fn render_error_page(
+    config: &Config,
+    locale: Locale,
+    code: &str,
+    title: &'static str,
+    description: &'static str,
+    icon: &str,
+) -> Markup {
     html! {
         (DOCTYPE)
-        html lang="es" {
+        html lang=(locale.code()) {
             head {
                 meta charset="utf-8";
                 meta name="viewport" content="width=device-width, initial-scale=1.0";
-                title { "404 - Página no encontrada | Tu Nombre" }
+                title { (code) " - " (locale.t(title)) " | " (config.site_name) }
                 meta http-equiv="X-Content-Type-Options" content="nosniff";
                 meta http-equiv="X-Frame-Options" content="DENY";
                 meta http-equiv="X-XSS-Protection" content="1; mode=block";
                 script src="https://unpkg.com/htmx.org@2.0.3" integrity="sha384-0895/pl2MU10Hqc6jd4RvrthNlDiE9U1tWmX7WRESftEDRosgxNsQG/Ze9YMRzHq" crossorigin="anonymous" {}
-                link rel="stylesheet" href="/static/css/electric-eclipse/main.css";
+                link rel="stylesheet" href=(asset_url("css/electric-eclipse/main.css"));
             }
             body {
                 main class="error-main" {
-                    section class="section error-section" {
-                        div class="container" {
-                            div class="content-card error-container" {
-                                h1 { "404" }
-                                h2 class="error-title" { "Página no encontrada" }
-                                p { "Lo sentimos, la página que buscas no existe o ha sido movida." }
-                                div class="error-button-container" {
-                                    a href=(Route::Home.path()) class="cta-button" { "← Volver al inicio" }
-                                }
-                                div class="error-icon-container" { "🔍" }
-                            }
-                        }
-                    }
+                    (render_error_content(locale, code, title, description, icon))
                 }
             }
         }
     }
 }
 
+fn render_error_content(
+    locale: Locale,
+    code: &str,
+    title: &'static str,
+    description: &'static str,
+    icon: &str,
+) -> Markup {
+    html! {
+        section class="section error-section" {
+            div class="container" {
+                div class="content-card error-container" {
+                    h1 { (code) }
+                    h2 class="error-title" { (locale.t(title)) }
+                    p { (locale.t(description)) }
+                    div class="error-button-container" {
+                        a href=(locale.path(&Route::Home.path())) class="cta-button" { (locale.t("error.back_home")) }
+                    }
+                    div class="error-icon-container" { (icon) }
+                }
+            }
+        }
+    }
+}
\ No newline at end of file
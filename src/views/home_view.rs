@@ -1,26 +1,33 @@
-use maud::{html, Markup};
+use maud::html;
+use crate::content::Content;
+use crate::i18n::Locale;
+use crate::og;
 use crate::routes::Route;
+use crate::views::page::{Page, PageMeta};
 
-pub fn render() -> Markup {
-    html! {
+pub fn render(content: &Content, locale: Locale) -> Page {
+    let profile = &content.profile;
+
+    let meta = PageMeta::new(&profile.title, locale.t("meta.description"), Route::Home.path())
+        .with_image(og::page_image_path(&Route::Home));
+
+    let markup = html! {
         section class="hero" {
             div class="container" {
                 div class="hero-content" {
-                    h1 class="hero-title" { "Hola, soy Tu Nombre" }
-                    p class="hero-subtitle" { "Desarrollador Full Stack" }
-                    p class="hero-description" {
-                        "Especializado en crear aplicaciones web modernas y eficientes utilizando "
-                        "tecnologías como Rust, JavaScript, React, y Node.js. "
-                        "Apasionado por el código limpio y las mejores prácticas de desarrollo."
-                    }
-                    a href=(Route::Contact.path())
-                      hx-get=(Route::Contact.path())
+                    h1 class="hero-title" { (locale.t_with("home.greeting", &[("name", &profile.name)])) }
+                    p class="hero-subtitle" { (profile.title) }
+                    p class="hero-description" { (profile.summary) }
+                    a href=(locale.path(&Route::Contact.path()))
+                      hx-get=(locale.path(&Route::Contact.path()))
                       hx-target="main"
                       hx-push-url="true"
                       class="btn btn-primary"
-                    { "Contactar" }
+                    { (locale.t("home.cta")) }
                 }
             }
         }
-    }
+    };
+
+    Page::new(meta, markup)
 }
\ No newline at end of file
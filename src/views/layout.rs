@@ -1,82 +1,149 @@
-use crate::{get_person_json_ld, get_website_json_ld, routes::Route};
+use crate::{
+    assets::asset_url,
+    config::Config,
+    content::Content,
+    csrf::CsrfToken,
+    feed,
+    i18n::{Lang, Locale},
+    routes::Route,
+    views::{page::Page, search_view},
+};
 use maud::{html, Markup, DOCTYPE};
 
-pub fn render_page_with_content(current_section: &str, content: Markup) -> Markup {
+/// `<title>` of a page: the site name first on the home page, last everywhere else
+fn document_title(config: &Config, page: &Page) -> String {
+    if page.meta.path == Route::Home.path() {
+        format!("{} - {}", config.site_name, page.meta.title)
+    } else {
+        format!("{} | {}", page.meta.title, config.site_name)
+    }
+}
+
+/// Page content for htmx navigation. htmx takes the `<title>` out of the response
+/// and sets it as the document title, so the tab follows the page.
+pub fn render_partial(config: &Config, page: Page) -> Markup {
+    html! {
+        title { (document_title(config, &page)) }
+        (page.content)
+    }
+}
+
+pub fn render_page_with_content(
+    config: &Config,
+    site: &Content,
+    csrf: &CsrfToken,
+    lang: &Lang,
+    current_section: &str,
+    page: Page,
+) -> Markup {
+    let locale = lang.locale;
+    let page_title = document_title(config, &page);
+    let meta = &page.meta;
+    let description = &meta.description;
+    let canonical = config.url(&locale.path(&meta.path));
+    let image = meta.image.as_deref().map(|image| config.url(&locale.path(image)));
+    let home = locale.path(&Route::Home.path());
+    let search_path = locale.path(&Route::Search.path());
+    let feed_title = locale.t_with("feed.title", &[("name", &config.site_name)]);
+
     html! {
         (DOCTYPE)
-        html lang="es" {
+        html lang=(locale.code()) {
             head {
                 meta charset="utf-8";
                 meta name="viewport" content="width=device-width, initial-scale=1.0";
-                title { "Tu Nombre - Desarrollador Full Stack" }
-                meta name="description" content="Portfolio profesional de desarrollador full stack especializado en Rust, JavaScript y tecnologías web modernas";
-                meta name="keywords" content="desarrollador full stack, rust, javascript, programación, portfolio, desarrollo web";
-                meta name="author" content="Tu Nombre";
-                meta name="robots" content="index, follow";
-                meta name="language" content="es";
+                title { (page_title) }
+                meta name="description" content=(description);
+                meta name="keywords" content=(locale.t("meta.keywords"));
+                meta name="author" content=(config.site_name);
+                meta name="robots" content=(meta.robots);
+                meta name="language" content=(locale.code());
                 meta name="revisit-after" content="7 days";
 
                 // Open Graph tags
                 meta property="og:type" content="website";
-                meta property="og:title" content="Tu Nombre - Desarrollador Full Stack";
-                meta property="og:description" content="Portfolio profesional de desarrollador full stack especializado en Rust, JavaScript y tecnologías web modernas";
-                meta property="og:url" content="https://tudominio.com";
-                meta property="og:site_name" content="Tu Nombre Portfolio";
-                meta property="og:locale" content="es_ES";
+                meta property="og:title" content=(page_title);
+                meta property="og:description" content=(description);
+                meta property="og:url" content=(canonical);
+                meta property="og:site_name" content=(locale.t_with("meta.site_name", &[("name", &config.site_name)]));
+                meta property="og:locale" content=(locale.og_locale());
+                @for other in Locale::ALL.into_iter().filter(|other| *other != locale) {
+                    meta property="og:locale:alternate" content=(other.og_locale());
+                }
+                @if let Some(image) = &image {
+                    meta property="og:image" content=(image);
+                }
 
                 // Twitter Cards
                 meta name="twitter:card" content="summary_large_image";
-                meta name="twitter:title" content="Tu Nombre - Desarrollador Full Stack";
-                meta name="twitter:description" content="Portfolio profesional de desarrollador full stack especializado en Rust, JavaScript y tecnologías web modernas";
+                meta name="twitter:title" content=(page_title);
+                meta name="twitter:description" content=(description);
                 meta name="twitter:creator" content="@tuusuario";
+                @if let Some(image) = &image {
+                    meta name="twitter:image" content=(image);
+                }
 
                 // Favicon and app icons
-                link rel="icon" type="image/x-icon" href="/static/favicon.ico";
-                link rel="icon" type="image/png" sizes="32x32" href="/static/favicon-32x32.png";
-                link rel="icon" type="image/png" sizes="16x16" href="/static/favicon-16x16.png";
-                link rel="apple-touch-icon" sizes="180x180" href="/static/apple-touch-icon.png";
-                link rel="manifest" href="/static/site.webmanifest";
+                link rel="icon" type="image/x-icon" href=(asset_url("favicon.ico"));
+                link rel="icon" type="image/png" sizes="32x32" href=(asset_url("favicon-32x32.png"));
+                link rel="icon" type="image/png" sizes="16x16" href=(asset_url("favicon-16x16.png"));
+                link rel="apple-touch-icon" sizes="180x180" href=(asset_url("apple-touch-icon.png"));
+                link rel="manifest" href=(asset_url("site.webmanifest"));
                 meta name="theme-color" content="#2563eb";
                 meta name="msapplication-TileColor" content="#2563eb";
 
                 // Canonical URL
-                link rel="canonical" href="https://tudominio.com";
+                link rel="canonical" href=(canonical);
+
+                // The same page in every language; the unprefixed URL picks one for the visitor
+                @for other in Locale::ALL {
+                    link rel="alternate" hreflang=(other.code()) href=(config.url(&other.path(&meta.path)));
+                }
+                link rel="alternate" hreflang="x-default" href=(config.url(&meta.path));
+
+                // Feed autodiscovery
+                link rel="alternate" type="application/rss+xml" title=(feed_title) href=(config.url(&locale.path(feed::RSS_PATH)));
+                link rel="alternate" type="application/atom+xml" title=(feed_title) href=(config.url(&locale.path(feed::ATOM_PATH)));
 
                 // CSP via meta tag (security headers now via HTTP headers)
                 meta http-equiv="Content-Security-Policy" content="default-src 'self'; script-src 'self'; style-src 'self' 'unsafe-inline'; img-src 'self' data: https:; connect-src 'self'; font-src 'self'; object-src 'none'; media-src 'self'; frame-src 'none'; base-uri 'self'; form-action 'self'";
 
                 // Structured Data (JSON-LD)
                 script type="application/ld+json" {
-                    (maud::PreEscaped(&get_website_json_ld()))
+                    (maud::PreEscaped(config.expand_json(&site.website_json_ld)))
                 }
                 script type="application/ld+json" {
-                    (maud::PreEscaped(&get_person_json_ld()))
+                    (maud::PreEscaped(config.expand_json(&site.person_json_ld)))
                 }
 
                 // Preload critical resources (optimized to prevent unused warnings)
-                link rel="preload" href="/static/css/electric-eclipse/main.css" as="style";
-                link rel="preload" href="/static/css/electric-eclipse/light.css" as="style";
-                link rel="preload" href="/static/js/htmx.min.js" as="script";
+                link rel="preload" href=(asset_url("css/electric-eclipse/main.css")) as="style";
+                link rel="preload" href=(asset_url("css/electric-eclipse/light.css")) as="style";
+                link rel="preload" href=(asset_url("js/htmx.min.js")) as="script";
                 link rel="dns-prefetch" href="//fonts.googleapis.com";
 
                 // Stylesheets
-                link rel="stylesheet" href="/static/css/electric-eclipse/main.css";
-                link id="theme-stylesheet" rel="stylesheet" href="/static/css/electric-eclipse/light.css";
+                link rel="stylesheet" href=(asset_url("css/electric-eclipse/main.css"));
+                // The theme scripts switch between the two fingerprinted URLs
+                link id="theme-stylesheet" rel="stylesheet" href=(asset_url("css/electric-eclipse/light.css"))
+                     data-light-href=(asset_url("css/electric-eclipse/light.css"))
+                     data-dark-href=(asset_url("css/electric-eclipse/dark.css"));
 
                 // Scripts
-                script src="/static/js/theme-init.js" defer {}
-                script src="/static/js/htmx.min.js" defer {}
+                script src=(asset_url("js/theme-init.js")) defer {}
+                script src=(asset_url("js/htmx.min.js")) defer {}
             }
-            body hx-boost="true" {
+            // htmx requests (boosted forms included) send the CSRF token as a header
+            body hx-boost="true" hx-headers=(csrf.hx_headers()) {
                 header {
                     div class="container" {
                         nav {
                             a class="logo"
-                                href=(Route::Home.path())
-                                hx-get=(Route::Home.path())
+                                href=(home)
+                                hx-get=(home)
                                 hx-target="main"
                                 hx-push-url="true"
-                            { "Tu Nombre" }
+                            { (config.site_name) }
                             
                             div class="htmx-indicator" {
                                 div class="loading-spinner" {}
@@ -84,40 +151,75 @@ pub fn render_page_with_content(current_section: &str, content: Markup) -> Marku
 
                             ul class="nav-links" id="nav-links" {
                                 li {
-                                    a href=(Route::About.path())
-                                      hx-get=(Route::About.path())
+                                    a href=(locale.path(&Route::About.path()))
+                                      hx-get=(locale.path(&Route::About.path()))
                                       hx-target="main"
                                       hx-push-url="true"
                                       class={ @if current_section == "about" { "nav-link active" } @else { "nav-link" } }
-                                    { "Sobre Mí" }
+                                    { (locale.t("nav.about")) }
                                 }
                                 li {
-                                    a href=(Route::Experience.path())
-                                      hx-get=(Route::Experience.path())
+                                    a href=(locale.path(&Route::Experience.path()))
+                                      hx-get=(locale.path(&Route::Experience.path()))
                                       hx-target="main"
                                       hx-push-url="true"
                                       class={ @if current_section == "experience" { "nav-link active" } @else { "nav-link" } }
-                                    { "Experiencia" }
+                                    { (locale.t("nav.experience")) }
                                 }
                                 li {
-                                    a href=(Route::Projects.path())
-                                      hx-get=(Route::Projects.path())
+                                    a href=(locale.path(&Route::Projects.path()))
+                                      hx-get=(locale.path(&Route::Projects.path()))
                                       hx-target="main"
                                       hx-push-url="true"
                                       class={ @if current_section == "projects" { "nav-link active" } @else { "nav-link" } }
-                                    { "Proyectos" }
+                                    { (locale.t("nav.projects")) }
+                                }
+                                li {
+                                    a href=(locale.path(&Route::Blog.path()))
+                                      hx-get=(locale.path(&Route::Blog.path()))
+                                      hx-target="main"
+                                      hx-push-url="true"
+                                      class={ @if current_section == "blog" { "nav-link active" } @else { "nav-link" } }
+                                    { (locale.t("nav.blog")) }
                                 }
                                 li {
-                                    a href=(Route::Contact.path())
-                                      hx-get=(Route::Contact.path())
+                                    a href=(locale.path(&Route::Contact.path()))
+                                      hx-get=(locale.path(&Route::Contact.path()))
                                       hx-target="main"
                                       hx-push-url="true"
                                       class={ @if current_section == "contact" { "nav-link active" } @else { "nav-link" } }
-                                    { "Contacto" }
+                                    { (locale.t("nav.contact")) }
                                 }
                             }
 
                             div class="nav-controls" {
+                                // Enter opens the results page; typing fills the dropdown
+                                form class="site-search" role="search" method="get" action=(search_path)
+                                     hx-get=(search_path) hx-target="main" hx-push-url="true" {
+                                    label class="visually-hidden" for="site-search-input" { (locale.t("search.label")) }
+                                    input type="search" id="site-search-input" name="q" class="site-search-input"
+                                          placeholder=(locale.t("search.placeholder_short")) autocomplete="off"
+                                          aria-controls=(search_view::DROPDOWN_ID)
+                                          hx-get=(search_path)
+                                          hx-trigger="input changed delay:250ms, search"
+                                          hx-target={ "#" (search_view::DROPDOWN_ID) }
+                                          hx-push-url="false";
+                                    div id=(search_view::DROPDOWN_ID) class="search-dropdown" aria-live="polite" {}
+                                }
+
+                                // Full page loads, so the document language and head change too
+                                nav class="language-switcher" aria-label=(locale.t("nav.language")) hx-boost="false" {
+                                    @for other in Locale::ALL {
+                                        a href=(lang.alternate(other))
+                                          hreflang=(other.code())
+                                          lang=(other.code())
+                                          title=(other.native_name())
+                                          class={ @if other == locale { "language-link active" } @else { "language-link" } }
+                                          aria-current=[(other == locale).then_some("true")]
+                                        { (other.code().to_uppercase()) }
+                                    }
+                                }
+
                                 button class="theme-toggle" id="theme-toggle" aria-label="Toggle theme" {
                                     svg class="theme-icon-sun" width="20" height="20" fill="currentColor" viewBox="0 0 20 20" {
                                         path fill-rule="evenodd" d="M10 2a1 1 0 011 1v1a1 1 0 11-2 0V3a1 1 0 011-1zm4 8a4 4 0 11-8 0 4 4 0 018 0zm-.464 4.95l.707.707a1 1 0 001.414-1.414l-.707-.707a1 1 0 00-1.414 1.414zm2.12-10.607a1 1 0 010 1.414l-.706.707a1 1 0 11-1.414-1.414l.707-.707a1 1 0 011.414 0zM17 11a1 1 0 100-2h-1a1 1 0 100 2h1zm-7 4a1 1 0 011 1v1a1 1 0 11-2 0v-1a1 1 0 011-1zM5.05 6.464A1 1 0 106.465 5.05l-.708-.707a1 1 0 00-1.414 1.414l.707.707zm1.414 8.486l-.707.707a1 1 0 01-1.414-1.414l.707-.707a1 1 0 011.414 1.414zM4 11a1 1 0 100-2H3a1 1 0 000 2h1z" clip-rule="evenodd" {}
@@ -138,20 +240,20 @@ pub fn render_page_with_content(current_section: &str, content: Markup) -> Marku
                 }
 
                 main hx-history-elt {
-                    (content)
+                    (page.content)
                 }
 
 
                 footer {
                     div class="container" {
                         p {
-                            "Made by "
+                            (locale.t("footer.made_by")) " "
                             a href="https://arkaitz.dev" target="_arkaitzdev_website" { "ArkaitzDev" }
                         }
                     }
                 }
 
-                script src="/static/js/main.js" defer {}
+                script src=(asset_url("js/main.js")) defer {}
             }
         }
     }
@@ -1,7 +1,12 @@
 pub mod layout;
+pub mod page;
+pub mod components;
 pub mod home_view;
 pub mod about_view;
 pub mod experience_view;
 pub mod projects_view;
+pub mod blog_view;
+pub mod search_view;
 pub mod contact_view;
-pub mod error_view;
\ No newline at end of file
+pub mod error_view;
+pub mod admin_view;
\ No newline at end of file
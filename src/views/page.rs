@@ -0,0 +1,64 @@
+use maud::Markup;
+
+/// Robots directives of public pages
+pub const INDEX: &str = "index, follow";
+/// Pages that should stay out of search results (admin, drafts)
+pub const NOINDEX: &str = "noindex, nofollow";
+
+/// Metadata of a page, rendered in the `<head>` by the layout
+pub struct PageMeta {
+    /// Name of the page; the layout adds the site name
+    pub title: String,
+    pub description: String,
+    /// Canonical path without the language prefix, query included (`/blog?page=2`)
+    pub path: String,
+    /// Open Graph image, a path on this site without the language prefix (see `og::image_path`)
+    pub image: Option<String>,
+    pub robots: &'static str,
+}
+
+impl PageMeta {
+    pub fn new(title: impl Into<String>, description: impl Into<String>, path: impl Into<String>) -> Self {
+        PageMeta {
+            title: title.into(),
+            description: description.into(),
+            path: path.into(),
+            image: None,
+            robots: INDEX,
+        }
+    }
+
+    pub fn with_image(mut self, image: impl Into<String>) -> Self {
+        self.image = Some(image.into());
+        self
+    }
+
+    pub fn noindex(mut self) -> Self {
+        self.robots = NOINDEX;
+        self
+    }
+}
+
+/// A rendered view and its metadata
+pub struct Page {
+    pub meta: PageMeta,
+    pub content: Markup,
+    /// Whether the rendered page can be reused for other requests (see `render_cache`)
+    pub cacheable: bool,
+}
+
+impl Page {
+    pub fn new(meta: PageMeta, content: Markup) -> Self {
+        Page {
+            meta,
+            content,
+            cacheable: true,
+        }
+    }
+
+    /// Rendered anew for every request, e.g. for a form with a single-use token
+    pub fn uncached(mut self) -> Self {
+        self.cacheable = false;
+        self
+    }
+}
@@ -1,187 +1,234 @@
 use maud::{html, Markup};
+use crate::content::{Content, Portfolio, Project};
+use crate::controllers::projects::ProjectFilter;
+use crate::i18n::Locale;
+use crate::og;
+use crate::routes::Route;
+use crate::views::components::nav_link;
+use crate::views::page::{Page, PageMeta};
 
-pub fn render() -> Markup {
-    html! {
+/// Element swapped by the filter bar and the tags
+pub const RESULTS_ID: &str = "project-results";
+const FILTER_FORM_ID: &str = "project-filter";
+
+pub fn render(content: &Content, filter: &ProjectFilter, locale: Locale) -> Page {
+    let portfolio = &content.portfolio;
+    let projects_path = locale.path(&Route::Projects.path());
+
+    let markup = html! {
         section class="section" {
             div class="container" {
                 div class="content-card" {
-                    h2 class="projects-title" { "Proyectos Destacados" }
-                    
-                    div class="projects-grid" {
-                        div class="project-card" {
-                            div class="project-image" {
-                                "🚀 E-Commerce Platform"
-                            }
-                            div class="project-content" {
-                                h3 class="project-title" { "Plataforma E-Commerce Avanzada" }
-                                p class="project-description" {
-                                    "Desarrollo completo de una plataforma de comercio electrónico con "
-                                    "procesamiento de pagos, gestión de inventario y panel de administración. "
-                                    "Maneja más de 10,000 transacciones mensuales."
-                                }
-                                div class="project-tags" {
-                                    span class="tag" { "Rust" }
-                                    span class="tag" { "React" }
-                                    span class="tag" { "PostgreSQL" }
-                                    span class="tag" { "Stripe" }
-                                    span class="tag" { "Docker" }
-                                }
-                                div class="project-links" {
-                                    a href="/projects/demo/ecommerce-platform" class="project-link" { "Ver Demo" }
-                                    a href="/projects/code/ecommerce-platform" class="project-link" { "Código" }
+                    h2 class="projects-title" { (locale.t("projects.title")) }
+
+                    // Without JavaScript the form reloads the page with the same query
+                    form id=(FILTER_FORM_ID) class="project-filter" role="search" method="get" action=(projects_path)
+                         hx-get=(projects_path)
+                         hx-target={ "#" (RESULTS_ID) }
+                         hx-trigger="submit, input delay:300ms"
+                         hx-push-url="true" {
+                        label class="visually-hidden" for="project-search" { (locale.t("projects.search_label")) }
+                        input type="search" id="project-search" name="q" value=[filter.query()]
+                              class="form-input" placeholder=(locale.t("projects.search_placeholder"));
+                        button type="submit" class="project-filter-button" { (locale.t("projects.search_button")) }
+                    }
+
+                    div id=(RESULTS_ID) aria-live="polite" {
+                        (results(portfolio, filter, locale))
+                    }
+
+                    div class="opensource-section" {
+                        h3 class="opensource-title" { (locale.t("projects.open_source")) }
+                        div class="opensource-grid" {
+                            @for contribution in &portfolio.open_source {
+                                div class="opensource-item" {
+                                    h4 class="opensource-item-title" { (contribution.name) }
+                                    p class="opensource-item-description" { (contribution.description) }
                                 }
                             }
                         }
-                        
-                        div class="project-card" {
-                            div class="project-image" {
-                                "📊 Analytics Dashboard"
-                            }
-                            div class="project-content" {
-                                h3 class="project-title" { "Dashboard de Analytics en Tiempo Real" }
-                                p class="project-description" {
-                                    "Sistema de análisis de datos en tiempo real con visualizaciones interactivas. "
-                                    "Procesa millones de eventos por día y proporciona insights empresariales "
-                                    "críticos a través de gráficos dinámicos."
-                                }
-                                div class="project-tags" {
-                                    span class="tag" { "Node.js" }
-                                    span class="tag" { "D3.js" }
-                                    span class="tag" { "WebSockets" }
-                                    span class="tag" { "MongoDB" }
-                                    span class="tag" { "Redis" }
-                                }
-                                div class="project-links" {
-                                    a href="/projects/demo/analytics-dashboard" class="project-link" { "Ver Demo" }
-                                    a href="/projects/code/analytics-dashboard" class="project-link" { "Código" }
-                                }
-                            }
+                    }
+                }
+            }
+        }
+    };
+
+    Page::new(meta(locale), markup)
+}
+
+/// Only the contents of the results element, for the htmx requests of the filter bar and the tags
+pub fn render_results(content: &Content, filter: &ProjectFilter, locale: Locale) -> Page {
+    Page::new(meta(locale), results(&content.portfolio, filter, locale))
+}
+
+// Filtered views are the same page: the canonical URL has no query
+fn meta(locale: Locale) -> PageMeta {
+    PageMeta::new(locale.t("projects.title"), locale.t("projects.description"), Route::Projects.path())
+        .with_image(og::page_image_path(&Route::Projects))
+}
+
+// Tags, count and grid. The current tag travels in a hidden field of the filter form,
+// so searching keeps it and clicking a tag (whose link carries the query) keeps the search.
+fn results(portfolio: &Portfolio, filter: &ProjectFilter, locale: Locale) -> Markup {
+    let projects = portfolio.filter(filter.tag(), filter.query());
+
+    html! {
+        @if let Some(tag) = filter.tag() {
+            input type="hidden" name="tag" value=(tag) form=(FILTER_FORM_ID);
+        }
+        div class="project-tags project-filter-tags" {
+            (tag_link(None, locale.t("projects.all_tags"), filter, locale))
+            @for (tag, count) in portfolio.tags() {
+                (tag_link(Some(tag), &format!("{} ({})", tag, count), filter, locale))
+            }
+        }
+        p class="project-filter-count" {
+            @if projects.is_empty() {
+                (locale.t("projects.no_results"))
+            } @else {
+                (locale.t_with("projects.count", &[
+                    ("count", &projects.len().to_string()),
+                    ("total", &portfolio.projects.len().to_string()),
+                ]))
+            }
+        }
+
+        div class="projects-grid" {
+            @for project in projects {
+                div class="project-card" {
+                    div class="project-image" {
+                        (project.icon) " " (project.label)
+                    }
+                    div class="project-content" {
+                        h3 class="project-title" {
+                            (nav_link(&locale.path(&Route::ProjectDetail(project.slug.clone()).path()), &project.title))
                         }
-                        
-                        div class="project-card" {
-                            div class="project-image" {
-                                "🎯 Task Manager"
-                            }
-                            div class="project-content" {
-                                h3 class="project-title" { "Gestor de Tareas Colaborativo" }
-                                p class="project-description" {
-                                    "Aplicación de gestión de proyectos con funcionalidades de colaboración "
-                                    "en tiempo real. Incluye chat integrado, notificaciones push y "
-                                    "sincronización offline-first."
-                                }
-                                div class="project-tags" {
-                                    span class="tag" { "Rust" }
-                                    span class="tag" { "Yew" }
-                                    span class="tag" { "WebAssembly" }
-                                    span class="tag" { "PWA" }
-                                    span class="tag" { "GraphQL" }
-                                }
-                                div class="project-links" {
-                                    a href="/projects/demo/task-manager" class="project-link" { "Ver Demo" }
-                                    a href="/projects/code/task-manager" class="project-link" { "Código" }
-                                }
+                        p class="project-description" { (project.description) }
+                        div class="project-tags" {
+                            @for tag in &project.tags {
+                                (tag_link(Some(tag), tag, filter, locale))
                             }
                         }
-                        
-                        div class="project-card" {
-                            div class="project-image" {
-                                "🌐 API Gateway"
-                            }
-                            div class="project-content" {
-                                h3 class="project-title" { "Microservices API Gateway" }
-                                p class="project-description" {
-                                    "Gateway de alta performance para arquitectura de microservicios con "
-                                    "rate limiting, autenticación, load balancing y monitoreo avanzado. "
-                                    "Soporta más de 100,000 requests por segundo."
-                                }
-                                div class="project-tags" {
-                                    span class="tag" { "Rust" }
-                                    span class="tag" { "Tokio" }
-                                    span class="tag" { "gRPC" }
-                                    span class="tag" { "Kubernetes" }
-                                    span class="tag" { "Prometheus" }
-                                }
-                                div class="project-links" {
-                                    a href="/projects/demo/api-gateway" class="project-link" { "Ver Demo" }
-                                    a href="/projects/code/api-gateway" class="project-link" { "Código" }
-                                }
-                            }
+                        (project_links(project, locale, true))
+                    }
+                }
+            }
+        }
+    }
+}
+
+// `/projects?tag=...&q=...` with the current search; `None` shows every tag
+fn tag_link(tag: Option<&str>, text: &str, filter: &ProjectFilter, locale: Locale) -> Markup {
+    let mut query = form_urlencoded::Serializer::new(String::new());
+    if let Some(tag) = tag {
+        query.append_pair("tag", tag);
+    }
+    if let Some(q) = filter.query() {
+        query.append_pair("q", q);
+    }
+    let query = query.finish();
+    let mut href = locale.path(&Route::Projects.path());
+    if !query.is_empty() {
+        href = format!("{}?{}", href, query);
+    }
+    let active = match (tag, filter.tag()) {
+        (Some(tag), Some(current)) => tag.eq_ignore_ascii_case(current),
+        (None, None) => true,
+        _ => false,
+    };
+
+    html! {
+        a href=(href)
+          hx-get=(href)
+          hx-target={ "#" (RESULTS_ID) }
+          hx-push-url="true"
+          class={ @if active { "tag active" } @else { "tag" } }
+        { (text) }
+    }
+}
+
+pub fn render_detail(project: &Project, locale: Locale) -> Page {
+    let meta = PageMeta::new(&project.title, &project.description, Route::ProjectDetail(project.slug.clone()).path())
+        .with_image(og::image_path(&project.slug));
+
+    let markup = html! {
+        section class="section" {
+            div class="container" {
+                article class="content-card project-detail" {
+                    p { (nav_link(&locale.path(&Route::Projects.path()), locale.t("projects.all_projects"))) }
+                    h2 class="projects-title" { (project.icon) " " (project.title) }
+                    @if let Some(role) = &project.role {
+                        p class="project-detail-role" {
+                            strong { (locale.t("projects.role")) ": " } (role)
                         }
-                        
-                        div class="project-card" {
-                            div class="project-image" {
-                                "📱 Mobile App"
-                            }
-                            div class="project-content" {
-                                h3 class="project-title" { "App Móvil de Fitness" }
-                                p class="project-description" {
-                                    "Aplicación móvil multiplataforma para tracking de ejercicios con "
-                                    "integración de wearables, planes personalizados y comunidad social. "
-                                    "Más de 50,000 usuarios activos."
-                                }
-                                div class="project-tags" {
-                                    span class="tag" { "React Native" }
-                                    span class="tag" { "TypeScript" }
-                                    span class="tag" { "Node.js" }
-                                    span class="tag" { "Firebase" }
-                                    span class="tag" { "ML Kit" }
-                                }
-                                div class="project-links" {
-                                    a href="/projects/demo/mobile-app" class="project-link" { "Ver Demo" }
-                                    a href="/projects/code/mobile-app" class="project-link" { "Código" }
-                                }
-                            }
+                    }
+
+                    div class="project-detail-body" {
+                        @if project.details.is_empty() {
+                            p { (project.description) }
                         }
-                        
-                        div class="project-card" {
-                            div class="project-image" {
-                                "🤖 AI Tool"
-                            }
-                            div class="project-content" {
-                                h3 class="project-title" { "Herramienta de IA para Code Review" }
-                                p class="project-description" {
-                                    "Sistema automatizado de revisión de código utilizando modelos de "
-                                    "lenguaje grandes. Analiza calidad, seguridad y mejores prácticas, "
-                                    "reduciendo el tiempo de review en un 60%."
-                                }
-                                div class="project-tags" {
-                                    span class="tag" { "Python" }
-                                    span class="tag" { "FastAPI" }
-                                    span class="tag" { "OpenAI" }
-                                    span class="tag" { "Docker" }
-                                    span class="tag" { "GitHub Actions" }
-                                }
-                                div class="project-links" {
-                                    a href="/projects/demo/ai-tool" class="project-link" { "Ver Demo" }
-                                    a href="/projects/code/ai-tool" class="project-link" { "Código" }
+                        @for paragraph in &project.details {
+                            p { (paragraph) }
+                        }
+                    }
+
+                    @if !project.screenshots.is_empty() {
+                        h3 class="project-detail-heading" { (locale.t("projects.screenshots")) }
+                        div class="project-gallery" {
+                            @for screenshot in &project.screenshots {
+                                figure class="project-screenshot" {
+                                    a href=(screenshot.src) hx-boost="false" {
+                                        img src=(screenshot.src) alt=(screenshot.alt) loading="lazy";
+                                    }
+                                    figcaption { (screenshot.alt) }
                                 }
                             }
                         }
                     }
-                    
-                    div class="opensource-section" {
-                        h3 class="opensource-title" { "Contribuciones Open Source" }
-                        div class="opensource-grid" {
-                            div class="opensource-item" {
-                                h4 class="opensource-item-title" { "Rocket Framework" }
-                                p class="opensource-item-description" { "Contribuciones al framework web de Rust" }
-                            }
-                            div class="opensource-item" {
-                                h4 class="opensource-item-title" { "Maud Templates" }
-                                p class="opensource-item-description" { "Mejoras en el sistema de templates" }
-                            }
-                            div class="opensource-item" {
-                                h4 class="opensource-item-title" { "htmx Library" }
-                                p class="opensource-item-description" { "Documentación y ejemplos" }
-                            }
-                            div class="opensource-item" {
-                                h4 class="opensource-item-title" { "Rust Ecosystem" }
-                                p class="opensource-item-description" { "Múltiples crates y utilidades" }
+
+                    h3 class="project-detail-heading" { (locale.t("projects.stack")) }
+                    div class="project-tags" {
+                        @for tag in &project.tags {
+                            span class="tag" { (tag) }
+                        }
+                    }
+
+                    @if !project.outcomes.is_empty() {
+                        h3 class="project-detail-heading" { (locale.t("projects.outcomes")) }
+                        ul class="project-outcomes" {
+                            @for outcome in &project.outcomes {
+                                li { (outcome) }
                             }
                         }
                     }
+
+                    (project_links(project, locale, false))
+                }
+            }
+        }
+    };
+
+    Page::new(meta, markup)
+}
+
+// Demo and repository links go through the redirects, only when the project has them.
+// They lead off the site, so htmx must not boost them
+fn project_links(project: &Project, locale: Locale, with_details: bool) -> Markup {
+    html! {
+        div class="project-links" {
+            @if with_details {
+                (nav_link(&locale.path(&Route::ProjectDetail(project.slug.clone()).path()), locale.t("projects.details")))
+            }
+            @if project.demo_url.is_some() {
+                a href=(locale.path(&Route::ProjectDemo(project.slug.clone()).path())) class="project-link" hx-boost="false" {
+                    (locale.t("projects.demo"))
+                }
+            }
+            @if project.repository_url.is_some() {
+                a href=(locale.path(&Route::ProjectCode(project.slug.clone()).path())) class="project-link" hx-boost="false" {
+                    (locale.t("projects.code"))
                 }
             }
         }
     }
-}
\ No newline at end of file
+}
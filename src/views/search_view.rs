@@ -0,0 +1,116 @@
+use maud::{html, Markup};
+use crate::i18n::Locale;
+use crate::routes::Route;
+use crate::search::{Hit, Kind};
+use crate::views::components::nav_link;
+use crate::views::page::{Page, PageMeta};
+
+/// Element of the header the search box fills as you type
+pub const DROPDOWN_ID: &str = "search-dropdown";
+
+/// Results listed in the dropdown; the results page shows all of them
+const DROPDOWN_RESULTS: usize = 5;
+
+pub fn results_path(query: &str, locale: Locale) -> String {
+    let query = form_urlencoded::Serializer::new(String::new()).append_pair("q", query).finish();
+    format!("{}?{}", locale.path(&Route::Search.path()), query)
+}
+
+pub fn render(hits: &[Hit], query: &str, locale: Locale) -> Page {
+    let title = if query.is_empty() {
+        locale.t("search.title").to_string()
+    } else {
+        locale.t_with("search.results_for", &[("query", query)])
+    };
+    let meta = PageMeta::new(title.as_str(), locale.t("search.description"), Route::Search.path()).noindex();
+    let search_path = locale.path(&Route::Search.path());
+
+    let markup = html! {
+        section class="section" {
+            div class="container" {
+                div class="content-card" {
+                    h2 class="search-title" { (title) }
+
+                    form class="search-form" role="search" method="get" action=(search_path)
+                         hx-get=(search_path) hx-target="main" hx-push-url="true" {
+                        label class="visually-hidden" for="search-page-input" { (locale.t("search.label")) }
+                        input type="search" id="search-page-input" name="q" value=(query)
+                              class="form-input" placeholder=(locale.t("search.placeholder"));
+                        button type="submit" class="search-button" { (locale.t("search.submit")) }
+                    }
+
+                    @if !query.is_empty() {
+                        p class="search-count" {
+                            @if hits.is_empty() {
+                                (locale.t("search.no_results"))
+                            } @else {
+                                (locale.t_with("search.count", &[("count", &hits.len().to_string())]))
+                            }
+                        }
+                    }
+
+                    div class="search-results" {
+                        @for hit in hits {
+                            article class="search-result" {
+                                span class="tag" { (kind_label(hit.document.kind, locale)) }
+                                h3 class="search-result-title" {
+                                    (nav_link(&locale.path(&hit.document.path), &hit.document.title))
+                                }
+                                p class="search-result-snippet" { (snippet(hit)) }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    Page::new(meta, markup)
+}
+
+/// Best results for the header dropdown; empty when there is no query, so it closes
+pub fn render_dropdown(hits: &[Hit], query: &str, locale: Locale) -> Markup {
+    html! {
+        @if !query.is_empty() {
+            div class="search-dropdown-panel" {
+                @if hits.is_empty() {
+                    p class="search-dropdown-empty" { (locale.t("search.no_results")) }
+                } @else {
+                    ul class="search-dropdown-list" {
+                        @for hit in hits.iter().take(DROPDOWN_RESULTS) {
+                            li {
+                                a href=(locale.path(&hit.document.path))
+                                  hx-get=(locale.path(&hit.document.path))
+                                  hx-target="main"
+                                  hx-push-url="true"
+                                  class="search-dropdown-item" {
+                                    span class="search-dropdown-kind" { (kind_label(hit.document.kind, locale)) }
+                                    span class="search-dropdown-title" { (hit.document.title) }
+                                    span class="search-dropdown-snippet" { (snippet(hit)) }
+                                }
+                            }
+                        }
+                    }
+                }
+                (nav_link(&results_path(query, locale), locale.t("search.all_results")))
+            }
+        }
+    }
+}
+
+fn kind_label(kind: Kind, locale: Locale) -> &'static str {
+    match kind {
+        Kind::Page => locale.t("search.kind_page"),
+        Kind::Project => locale.t("search.kind_project"),
+        Kind::Experience => locale.t("search.kind_experience"),
+        Kind::Post => locale.t("search.kind_post"),
+    }
+}
+
+fn snippet(hit: &Hit) -> Markup {
+    html! {
+        @for (text, matched) in &hit.snippet {
+            @if *matched { mark { (text) } } @else { (text) }
+        }
+    }
+}
@@ -26,10 +26,10 @@ const ThemeManager = {
     // Prevent flash of unstyled content
     document.documentElement.style.setProperty('--transition-theme', 'none');
     
-    // Update stylesheet
+    // Update stylesheet (fingerprinted URLs from the page, plain paths as a fallback)
     const newHref = theme === "dark" 
-      ? "/static/css/electric-eclipse/dark.css" 
-      : "/static/css/electric-eclipse/light.css";
+      ? themeStylesheet.dataset.darkHref || "/static/css/electric-eclipse/dark.css" 
+      : themeStylesheet.dataset.lightHref || "/static/css/electric-eclipse/light.css";
     
     themeStylesheet.setAttribute("href", newHref);
     
@@ -500,12 +500,35 @@ const LoadingManager = {
   }
 };
 
+// Header search: close the live results on Escape or a click elsewhere
+const SearchDropdown = {
+  close() {
+    const dropdown = document.getElementById("search-dropdown");
+    if (dropdown) dropdown.innerHTML = "";
+  },
+
+  init() {
+    document.addEventListener("keydown", (e) => {
+      if (e.key === "Escape" && e.target.closest?.(".site-search")) {
+        this.close();
+      }
+    });
+
+    document.addEventListener("click", (e) => {
+      if (!e.target.closest(".site-search")) {
+        this.close();
+      }
+    });
+  }
+};
+
 // Initialize everything when DOM is ready
 document.addEventListener("DOMContentLoaded", () => {
   ThemeManager.init();
   NavigationManager.init();
   AccessibilityManager.init();
   LoadingManager.init();
+  SearchDropdown.init();
   
   // Setup theme toggle button
   const themeToggle = document.getElementById("theme-toggle");
@@ -521,11 +544,22 @@ document.addEventListener("DOMContentLoaded", () => {
 document.body.addEventListener("htmx:beforeSwap", (e) => {
   // Add loading class for smooth transitions
   document.body.classList.add("htmx-loading");
+
+  // Missing pages, rejected (CSRF) and rate-limited requests come back with a
+  // rendered page, show it instead of ignoring it
+  if ([404, 403, 429].includes(e.detail.xhr.status)) {
+    e.detail.shouldSwap = true;
+    e.detail.isError = false;
+  }
 });
 
 document.body.addEventListener("htmx:afterSwap", (e) => {
   // Remove loading class
   document.body.classList.remove("htmx-loading");
+
+  // Live search results only fill the header dropdown, the page stays where it is
+  if (e.detail.target.id === "search-dropdown") return;
+  SearchDropdown.close();
   
   // Re-initialize dynamic elements
   AccessibilityManager.enhanceStatusMessages();
@@ -18,7 +18,10 @@
         
         const themeStylesheet = document.getElementById("theme-stylesheet");
         if (themeStylesheet) {
-            themeStylesheet.setAttribute("href", validatedTheme === "dark" ? "/static/css/electric-eclipse/dark.css" : "/static/css/electric-eclipse/light.css");
+            const href = validatedTheme === "dark"
+                ? themeStylesheet.dataset.darkHref || "/static/css/electric-eclipse/dark.css"
+                : themeStylesheet.dataset.lightHref || "/static/css/electric-eclipse/light.css";
+            themeStylesheet.setAttribute("href", href);
         }
         
         // Set data attribute for CSS targeting